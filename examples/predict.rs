@@ -16,18 +16,25 @@ fn main() {
     };
 
     let location: Location = Location{lat_deg:58.64560, lon_deg: 23.15163, alt_m: 8.};
-    let mut predict: Predict = Predict::new(&tle, &location);
+    let mut predict: Predict = match Predict::new(&tle, &location) {
+        Ok(predict) => predict,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
 
     loop {
-        // these two are the same:
-        predict.update(None);
-
-        println!("aos        : {:}", predict.sat.aos.expect("do not have AOS with this satellite").as_gregorian_utc_str());
-        println!("los        : {:}", predict.sat.los.expect("do not have LOS with this satellite").as_gregorian_utc_str());
-        println!("az         : {:.2}°", predict.sat.az_deg);
-        println!("el         : {:.2}°", predict.sat.el_deg);
-        println!("range      : {:.0} km", predict.sat.range_km);
-        println!("range rate : {:.3} km/sec\n", predict.sat.range_rate_km_sec);
+        if let Err(e) = predict.update_now() {
+            println!("{}", e);
+            return;
+        }
+
+        // with serde, print one JSON object per line for other tools to consume
+        #[cfg(feature = "serde")]
+        println!("{}", predict.sat.to_json());
+        #[cfg(not(feature = "serde"))]
+        println!("{}\n", predict.sat);
 
         thread::sleep(Duration::from_secs(1));
     }
@@ -0,0 +1,91 @@
+extern crate chrono;
+extern crate gpredict;
+
+use chrono::Utc;
+use gpredict::{format_local, Clock, Location, Predict, SystemClock};
+use gpredict::tle;
+
+use std::env;
+use std::fs;
+
+const USAGE: &str = "usage: predict_all <tle file> <lat_deg> <lon_deg> [alt_m]";
+
+// passes above this elevation are listed after the next pass
+const GOOD_PASS_ELEVATION_DEG: f64 = 20.;
+const GOOD_PASS_COUNT: usize = 3;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 4 {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let coordinate = |i: usize| args.get(i).map(|a| a.parse::<f64>());
+    let location = match (coordinate(2), coordinate(3), coordinate(4).unwrap_or(Ok(0.))) {
+        (Some(Ok(lat)), Some(Ok(lon)), Ok(alt)) => match Location::new(lat, lon, alt) {
+            Ok(location) => location,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+        _ => {
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    let input = match fs::read_to_string(&args[1]) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("{}: {}", args[1], e);
+            return;
+        }
+    };
+
+    let now = SystemClock.now();
+    let mut passes = Vec::new();
+    let mut never = Vec::new();
+
+    for tle in tle::parse_multiple(&input) {
+        let tle = match tle {
+            Ok(tle) => tle,
+            Err(e) => {
+                println!("skipping element set: {}", e);
+                continue;
+            }
+        };
+
+        let mut predict = match Predict::new(&tle, location) {
+            Ok(predict) => predict,
+            Err(e) => {
+                println!("{}: {}", tle.name, e);
+                continue;
+            }
+        };
+
+        match predict.next_pass(now) {
+            Some(pass) => {
+                let good = predict.upcoming_passes(now, GOOD_PASS_COUNT, GOOD_PASS_ELEVATION_DEG);
+                passes.push((tle.name, pass, good));
+            }
+            None => never.push(tle.name),
+        }
+    }
+
+    passes.sort_by_key(|p| p.1.aos);
+
+    for (name, pass, good) in &passes {
+        println!("{:<24} AOS {} LOS {} max el {:5.1}°",
+                 name, format_local(pass.aos, &Utc), format_local(pass.los, &Utc), pass.max_elevation_deg);
+
+        for p in good {
+            println!("{:<24}   {:.0}°+ pass at {}", "", GOOD_PASS_ELEVATION_DEG, format_local(p.culmination, &Utc));
+        }
+    }
+
+    for name in &never {
+        println!("{:<24} no pass within the search window", name);
+    }
+}
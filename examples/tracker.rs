@@ -0,0 +1,38 @@
+extern crate gpredict;
+
+use gpredict::{Predict, Location, Tle, Tracker, TrackerEvent};
+
+use std::time::Duration;
+
+fn main() {
+    let tle: Tle = Tle {
+        name: "GRIFEX".to_string(),
+        line1: "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993".to_string(),
+        line2: "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889".to_string()
+    };
+
+    let location: Location = Location{lat_deg:58.64560, lon_deg: 23.15163, alt_m: 8.};
+    let predict: Predict = match Predict::new(&tle, &location) {
+        Ok(predict) => predict,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    println!("waiting for {}", tle.name);
+
+    let mut tracker = Tracker::new(predict);
+    let result = tracker.run(Duration::from_secs(1), |event, sat| {
+        match event {
+            TrackerEvent::Aos => println!("AOS"),
+            TrackerEvent::Sample => println!("{}\n", sat),
+            TrackerEvent::Los => println!("LOS"),
+        }
+        true
+    });
+
+    if let Err(e) = result {
+        println!("{}", e);
+    }
+}
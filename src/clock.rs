@@ -0,0 +1,195 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::error::Error;
+use std::fmt::{self, Debug};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, ParseError, ParseErrorKind, SecondsFormat, TimeZone, Utc};
+
+use ::ffipredict;
+
+/// Source of the current time for `Predict::update_now` and `Tracker`.
+pub trait Clock: Debug {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// System time, the default clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        from_julian_date(unsafe {ffipredict::get_current_daynum()})
+    }
+}
+
+/// Always returns the same time, for deterministic tests.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Error parsing a time, see `parse_time`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseTimeError {
+    /// not of the form "YYYY-MM-DD[THH:MM[:SS[.sss]]][Z|±HH:MM]"
+    BadFormat,
+
+    /// a field is out of range, e.g. month 13 or February 29 in 2015
+    OutOfRange,
+}
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseTimeError::BadFormat => write!(f, "time is not in ISO 8601 format"),
+            ParseTimeError::OutOfRange => write!(f, "time field is out of range"),
+        }
+    }
+}
+
+impl Error for ParseTimeError {}
+
+/// Parses an ISO 8601 / RFC 3339 time such as "2015-09-01T03:00:00+03:00".
+///
+/// The time of day, seconds, fraction and offset are optional, a space can
+/// be used instead of the "T" and a missing offset or " UTC" mean UTC.
+pub fn parse_time(s: &str) -> Result<DateTime<Utc>, ParseTimeError> {
+    let s = s.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+
+    let (s, utc) = match s.strip_suffix(" UTC").or_else(|| s.strip_suffix('Z')) {
+        Some(s) => (s, true),
+        None => (s, false),
+    };
+    let s = s.replacen(' ', "T", 1);
+
+    let mut error = ParseTimeError::BadFormat;
+    let mut note = |e: ParseError| if let ParseErrorKind::OutOfRange | ParseErrorKind::Impossible = e.kind() {
+        error = ParseTimeError::OutOfRange;
+    };
+    if !utc {
+        for format in &["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%dT%H:%M%z"] {
+            match DateTime::parse_from_str(&s, format) {
+                Ok(t) => return Ok(t.with_timezone(&Utc)),
+                Err(e) => note(e),
+            }
+        }
+    }
+    for format in &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"] {
+        match NaiveDateTime::parse_from_str(&s, format) {
+            Ok(t) => return Ok(Utc.from_utc_datetime(&t)),
+            Err(e) => note(e),
+        }
+    }
+    match NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        Ok(d) => return Ok(Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap())),
+        Err(e) => note(e),
+    }
+
+    Err(error)
+}
+
+/// Julian date of the Unix epoch, 1970-01-01T00:00:00Z
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+/// Julian date (UTC) of `t`, the time scale used by the prediction code.
+pub fn to_julian_date(t: DateTime<Utc>) -> f64 {
+    let secs = t.timestamp() as f64 + t.timestamp_subsec_nanos() as f64 * 1e-9;
+    secs / 86400.0 + UNIX_EPOCH_JD
+}
+
+/// Inverse of `to_julian_date`, rounded to the nearest microsecond. Near the
+/// present an f64 Julian date only resolves about 40 µs.
+pub fn from_julian_date(jd: f64) -> DateTime<Utc> {
+    let micros = ((jd - UNIX_EPOCH_JD) * 86400e6).round() as i64;
+    DateTime::<Utc>::from_timestamp(micros.div_euclid(1_000_000), micros.rem_euclid(1_000_000) as u32 * 1000)
+        .expect("julian date out of range")
+}
+
+/// Formats `t` in the time zone `tz` to whole seconds, e.g.
+/// "2015-09-01T03:00:00+03:00" for `FixedOffset::east_opt(3 * 3600)`.
+pub fn format_local<Tz: TimeZone>(t: DateTime<Utc>, tz: &Tz) -> String
+    where Tz::Offset: fmt::Display {
+    t.with_timezone(tz).to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+#[test]
+fn clock_julian_date() {
+    let t = from_julian_date(2457266.5);
+    assert_eq!(t.timestamp(), 1441065600);
+    assert_eq!(to_julian_date(t), 2457266.5);
+    assert_eq!(to_julian_date(from_julian_date(2457266.75)), 2457266.75);
+    assert_eq!(from_julian_date(2440587.25).timestamp(), -21600);
+    assert_eq!(from_julian_date(2440587.25).timestamp_subsec_nanos(), 0);
+}
+
+#[test]
+fn clock_format_local() {
+    use chrono::FixedOffset;
+
+    let t = from_julian_date(2457266.5);
+
+    assert_eq!(format_local(t, &Utc), "2015-09-01T00:00:00+00:00");
+    assert_eq!(format_local(t, &FixedOffset::east_opt(3 * 3600).unwrap()), "2015-09-01T03:00:00+03:00");
+    assert_eq!(format_local(t, &FixedOffset::west_opt(5 * 3600 + 1800).unwrap()), "2015-08-31T18:30:00-05:30");
+}
+
+#[test]
+fn clock_parse_time() {
+    let jd = |s: &str| to_julian_date(parse_time(s).unwrap());
+
+    assert_eq!(jd("2015-09-01T00:00:00Z"), 2457266.5);
+    assert_eq!(jd("2015-09-01"), 2457266.5);
+    assert_eq!(jd("2015-09-01 00:00:00 UTC"), 2457266.5);
+    assert_eq!(jd("2015-09-01T03:00:00+03:00"), 2457266.5);
+    assert_eq!(jd("2015-08-31T18:30-0530"), 2457266.5);
+    assert!((jd("2015-09-01T12:00:00.5Z") - 2457267.0 - 0.5 / 86400.).abs() < 1e-9);
+    assert_eq!(jd("2016-02-29T00:00:00Z"), 2457447.5);
+
+    let t = parse_time("2015-08-31T18:30:00-05:30").unwrap();
+    assert_eq!(format_local(t, &chrono::FixedOffset::west_opt(5 * 3600 + 1800).unwrap()), "2015-08-31T18:30:00-05:30");
+
+    assert_eq!(parse_time("yesterday"), Err(ParseTimeError::BadFormat));
+    assert_eq!(parse_time("2015-13-01"), Err(ParseTimeError::OutOfRange));
+    assert_eq!(parse_time("2015-02-29"), Err(ParseTimeError::OutOfRange));
+    assert_eq!(parse_time("2015-09-01T25:00Z"), Err(ParseTimeError::OutOfRange));
+    assert!(parse_time("2015-09-0é").is_err());
+    assert!(parse_time("2015-09-01T00:00+0é0").is_err());
+    assert!(parse_time("2015-09-01Té0:00").is_err());
+}
+
+#[test]
+fn clock_parse_time_micros() {
+    let t = parse_time("2015-09-01T03:00:00.123456Z").unwrap();
+    assert_eq!(t.timestamp_subsec_micros(), 123456);
+    assert_eq!(t.to_rfc3339_opts(SecondsFormat::Micros, true), "2015-09-01T03:00:00.123456Z");
+
+    assert_eq!(parse_time("2015-09-01 03:00:00.123456 UTC"), Ok(t));
+}
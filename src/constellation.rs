@@ -0,0 +1,148 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use chrono::{DateTime, Utc};
+use coordinates::LLA;
+
+use ::tle::Tle;
+use ::location::Location;
+use ::predict::{Predict, PredictError};
+use ::sat::Sat;
+use ::coords::{self, EARTH_RADIUS_KM};
+
+/// Several satellites tracked from the same location.
+#[derive(Debug)]
+pub struct Constellation {
+    location: Location,
+    predicts: Vec<Predict>,
+}
+
+impl Constellation {
+    pub fn new<T: Into<LLA>>(location: T) -> Constellation {
+        Constellation { location: Location::from(location.into()), predicts: Vec::new() }
+    }
+
+    /// Adds a satellite tracked from the constellation's location.
+    pub fn add(&mut self, tle: &Tle) -> Result<(), PredictError> {
+        let predict = Predict::new(tle, self.location)?;
+        self.predicts.push(predict);
+        Ok(())
+    }
+
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    pub fn predicts(&self) -> &[Predict] {
+        &self.predicts
+    }
+
+    /// Moves the observer of every satellite to `location`.
+    ///
+    /// There is no mutable access to the members, so they always share the
+    /// constellation's location.
+    pub fn set_location<T: Into<LLA>>(&mut self, location: T) {
+        self.location = Location::from(location.into());
+        for predict in self.predicts.iter_mut() {
+            predict.set_location(self.location);
+        }
+    }
+
+    /// Updates the `sat` of every satellite for the given time.
+    ///
+    /// All satellites are updated even if some fail, the first error is
+    /// returned.
+    pub fn update_all(&mut self, t: DateTime<Utc>) -> Result<(), PredictError> {
+        let mut result = Ok(());
+        for predict in self.predicts.iter_mut() {
+            let updated = predict.update_at(t);
+            if result.is_ok() {
+                result = updated;
+            }
+        }
+
+        result
+    }
+
+    /// Satellites above the geometric horizon as of the last `update_all`.
+    pub fn currently_visible(&self) -> Vec<&Sat> {
+        self.predicts.iter()
+                     .map(|predict| &predict.sat)
+                     .filter(|sat| sat.el_deg > 0.0)
+                     .collect()
+    }
+}
+
+/// Returns true if satellites `a` and `b` can see each other at time `t`, e.g.
+/// for inter-satellite links.
+///
+/// The line between them has to pass at least `margin_km` above the earth's
+/// equatorial radius, e.g. to keep the link out of the atmosphere.
+pub fn intersat_visible(a: &mut Predict, b: &mut Predict, t: DateTime<Utc>, margin_km: f64) -> bool {
+    let pos_a = a.observe_at(t).eci_position_km();
+    let pos_b = b.observe_at(t).eci_position_km();
+
+    coords::line_of_sight(&pos_a, &pos_b, EARTH_RADIUS_KM + margin_km)
+}
+
+#[test]
+fn constellation_update_all() {
+    use ::clock::from_julian_date;
+
+    let (tle, location) = ::grifex();
+    let mut constellation = Constellation::new(location);
+    constellation.add(&tle).unwrap();
+    constellation.add(&tle).unwrap();
+
+    // culmination of a pass over Tartu
+    constellation.update_all(from_julian_date(2457266.81187298)).unwrap();
+    let predicts = constellation.predicts();
+    assert!(predicts[0].sat.el_deg > 0.);
+    assert_eq!(predicts[0].sat.el_deg, predicts[1].sat.el_deg);
+    assert_eq!(predicts[0].sat.los, predicts[1].sat.los);
+}
+
+#[test]
+fn constellation_currently_visible() {
+    use ::clock::from_julian_date;
+
+    let (tle, location) = ::grifex();
+    let mut constellation = Constellation::new(location);
+    assert!(constellation.currently_visible().is_empty());
+
+    constellation.add(&tle).unwrap();
+    let culmination = from_julian_date(2457266.81187298);
+    constellation.update_all(culmination).unwrap();
+    assert_eq!(constellation.currently_visible().len(), 1);
+
+    constellation.update_all(from_julian_date(2457266.84)).unwrap();
+    assert!(constellation.currently_visible().is_empty());
+
+    // moving the constellation moves its members
+    let santiago = Location { lat_deg: -33.4, lon_deg: -70.7, alt_m: 500. };
+    constellation.set_location(santiago);
+    constellation.update_all(culmination).unwrap();
+    assert_eq!(constellation.location(), santiago);
+    assert!(constellation.currently_visible().is_empty());
+}
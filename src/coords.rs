@@ -0,0 +1,329 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::f64::consts::PI;
+use chrono::{DateTime, Utc};
+
+use ::clock::to_julian_date;
+
+/// earth equatorial radius (WGS-72) [km]
+pub const EARTH_RADIUS_KM: f64 = 6378.135;
+
+/// earth flattening (WGS-72)
+pub const EARTH_FLATTENING: f64 = 3.35281066474748e-3;
+
+/// earth gravitational parameter (WGS-72) [km^3/s^2]
+pub const EARTH_GM_KM3_S2: f64 = 398600.8;
+
+/// earth rotation rate [rad/s]
+pub const EARTH_ROTATION_RAD_S: f64 = 7.292115e-5;
+
+/// J2 harmonic (WGS-72) times AE^2 / 2
+pub(crate) const CK2: f64 = 5.413079e-4;
+
+/// sqrt(GM) in earth radii^1.5 / min (WGS-72)
+pub(crate) const XKE: f64 = 7.43669161e-2;
+
+/// Reference ellipsoid for the geometry computed on the Rust side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarthModel {
+    /// equatorial radius [km]
+    pub radius_km:  f64,
+
+    /// flattening
+    pub flattening: f64,
+}
+
+/// the ellipsoid SGP4 and gpredict use
+pub const WGS72: EarthModel = EarthModel { radius_km: EARTH_RADIUS_KM, flattening: EARTH_FLATTENING };
+
+impl Default for EarthModel {
+    fn default() -> EarthModel {
+        WGS72
+    }
+}
+
+impl EarthModel {
+    /// Earth fixed position [km] of a geodetic location.
+    pub fn geodetic_to_ecef(&self, lat_deg: f64, lon_deg: f64, alt_km: f64) -> [f64; 3] {
+        let (sin_lat, cos_lat) = lat_deg.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = lon_deg.to_radians().sin_cos();
+        let f = self.flattening;
+        let c = 1.0 / (1.0 + f * (f - 2.0) * sin_lat * sin_lat).sqrt();
+        let sq = (1.0 - f).powi(2) * c;
+        let achcp = (self.radius_km * c + alt_km) * cos_lat;
+
+        [achcp * cos_lon, achcp * sin_lon, (self.radius_km * sq + alt_km) * sin_lat]
+    }
+
+    /// Geodetic `(lat_deg, lon_deg, alt_km)` of an earth fixed position [km],
+    /// longitude within [-180°, 180°].
+    pub fn ecef_to_geodetic(&self, ecef: &[f64; 3]) -> (f64, f64, f64) {
+        let e2 = self.flattening * (2.0 - self.flattening);
+        let r = (ecef[0] * ecef[0] + ecef[1] * ecef[1]).sqrt();
+        let lon = ecef[1].atan2(ecef[0]);
+
+        // fixed point iteration on the latitude, converges in a few steps
+        let mut lat = ecef[2].atan2(r);
+        let mut c = 1.0;
+        for _ in 0..20 {
+            let phi = lat;
+            c = 1.0 / (1.0 - e2 * phi.sin() * phi.sin()).sqrt();
+            lat = (ecef[2] + self.radius_km * c * e2 * phi.sin()).atan2(r);
+            if (lat - phi).abs() < 1e-12 {
+                break;
+            }
+        }
+
+        // close to the poles the height is better conditioned along z
+        let alt = if lat.abs() < PI / 4.0 {
+            r / lat.cos() - self.radius_km * c
+        }
+        else {
+            ecef[2] / lat.sin() - self.radius_km * c * (1.0 - e2)
+        };
+
+        (lat.to_degrees(), lon.to_degrees(), alt)
+    }
+
+    /// ECI position [km] of a geodetic location at Julian date `jd`.
+    pub fn geodetic_to_eci(&self, lat_deg: f64, lon_deg: f64, alt_km: f64, jd: f64) -> [f64; 3] {
+        self.geodetic_to_ecef(lat_deg, lon_deg + gmst_jd(jd).to_degrees(), alt_km)
+    }
+}
+
+/// Greenwich mean sidereal time [rad] at Julian date `jd`, same as gpredict's ThetaG_JD.
+pub fn gmst_jd(jd: f64) -> f64 {
+    let ut = (jd + 0.5).fract();
+    let tu = (jd - ut - 2451545.0) / 36525.0;
+    let gmst = 24110.54841 + tu * (8640184.812866 + tu * (0.093104 - tu * 6.2e-6));
+    let gmst = (gmst + 86400.0 * 1.00273790934 * ut).rem_euclid(86400.0);
+
+    2.0 * PI * gmst / 86400.0
+}
+
+/// Greenwich mean sidereal time [rad] at `t`, the same the predictions use
+/// for converting between the ECI and earth fixed frames.
+pub fn gmst(t: DateTime<Utc>) -> f64 {
+    gmst_jd(to_julian_date(t))
+}
+
+/// ECI position [km] of a geodetic location (WGS-72) at Julian date `jd`.
+pub fn geodetic_to_eci(lat_deg: f64, lon_deg: f64, alt_km: f64, jd: f64) -> [f64; 3] {
+    WGS72.geodetic_to_eci(lat_deg, lon_deg, alt_km, jd)
+}
+
+/// Earth fixed position [km] of a geodetic location (WGS-72).
+pub fn geodetic_to_ecef(lat_deg: f64, lon_deg: f64, alt_km: f64) -> [f64; 3] {
+    WGS72.geodetic_to_ecef(lat_deg, lon_deg, alt_km)
+}
+
+/// Geodetic `(lat_deg, lon_deg, alt_km)` (WGS-72) of an earth fixed position [km].
+pub fn ecef_to_geodetic(ecef: &[f64; 3]) -> (f64, f64, f64) {
+    WGS72.ecef_to_geodetic(ecef)
+}
+
+/// Rotates an ECI vector into the earth fixed frame at Julian date `jd`.
+pub fn eci_to_ecef(eci: &[f64; 3], jd: f64) -> [f64; 3] {
+    let (sin_theta, cos_theta) = gmst_jd(jd).sin_cos();
+
+    [cos_theta * eci[0] + sin_theta * eci[1],
+     -sin_theta * eci[0] + cos_theta * eci[1],
+     eci[2]]
+}
+
+/// Range vector [km] from a geodetic location to an ECI position at Julian
+/// date `jd` in the topocentric (south, east, zenith) frame.
+pub fn eci_to_topocentric(lat_deg: f64, lon_deg: f64, alt_km: f64, target_km: &[f64; 3], jd: f64) -> [f64; 3] {
+    let observer = geodetic_to_eci(lat_deg, lon_deg, alt_km, jd);
+    let range = [target_km[0] - observer[0], target_km[1] - observer[1], target_km[2] - observer[2]];
+
+    let (sin_lat, cos_lat) = lat_deg.to_radians().sin_cos();
+    let (sin_theta, cos_theta) = (gmst_jd(jd) + lon_deg.to_radians()).sin_cos();
+
+    [sin_lat * cos_theta * range[0] + sin_lat * sin_theta * range[1] - cos_lat * range[2],
+     -sin_theta * range[0] + cos_theta * range[1],
+     cos_lat * cos_theta * range[0] + cos_lat * sin_theta * range[1] + sin_lat * range[2]]
+}
+
+/// Topocentric `(az_deg, el_deg, range_km)` of an ECI position seen from a
+/// geodetic location at Julian date `jd`.
+pub fn look_angles(lat_deg: f64, lon_deg: f64, alt_km: f64, target_km: &[f64; 3], jd: f64) -> (f64, f64, f64) {
+    let sez = eci_to_topocentric(lat_deg, lon_deg, alt_km, target_km, jd);
+    let (top_s, top_e, top_z) = (sez[0], sez[1], sez[2]);
+    let range_km = magnitude(&sez);
+
+    let mut az = (-top_e / top_s).atan();
+    if top_s > 0.0 {
+        az += PI;
+    }
+    if az < 0.0 {
+        az += 2.0 * PI;
+    }
+    let el = (top_z / range_km).asin();
+
+    (az.to_degrees(), el.to_degrees(), range_km)
+}
+
+/// Rates `(az_deg_s, el_deg_s)` of the look angles to an ECI position moving
+/// with `target_vel_km_s`, by a central difference over one second.
+pub fn look_angle_rates(lat_deg: f64, lon_deg: f64, alt_km: f64, target_km: &[f64; 3],
+                        target_vel_km_s: &[f64; 3], jd: f64) -> (f64, f64) {
+    let dt = 0.5;
+    let at = |offset: f64| {
+        let target = [target_km[0] + target_vel_km_s[0] * offset,
+                      target_km[1] + target_vel_km_s[1] * offset,
+                      target_km[2] + target_vel_km_s[2] * offset];
+        look_angles(lat_deg, lon_deg, alt_km, &target, jd + offset / 86400.0)
+    };
+
+    let (az0, el0, _) = at(-dt);
+    let (az1, el1, _) = at(dt);
+
+    // shortest way around for azimuths on both sides of north
+    let daz = (az1 - az0 + 540.0) % 360.0 - 180.0;
+
+    (daz / (2.0 * dt), (el1 - el0) / (2.0 * dt))
+}
+
+/// Topocentric `(ra_deg, dec_deg)` of an ECI position seen from a geodetic
+/// location at Julian date `jd`.
+pub fn ra_dec(lat_deg: f64, lon_deg: f64, alt_km: f64, target_km: &[f64; 3], jd: f64) -> (f64, f64) {
+    let observer = geodetic_to_eci(lat_deg, lon_deg, alt_km, jd);
+    let range = [target_km[0] - observer[0], target_km[1] - observer[1], target_km[2] - observer[2]];
+
+    let mut ra = range[1].atan2(range[0]);
+    if ra < 0.0 {
+        ra += 2.0 * PI;
+    }
+    let dec = (range[2] / magnitude(&range)).asin();
+
+    (ra.to_degrees(), dec.to_degrees())
+}
+
+/// Returns true if the geocentric latitude of an ECI position moving with
+/// `vel` is increasing.
+pub fn is_northbound(pos: &[f64; 3], vel: &[f64; 3]) -> bool {
+    // sign of d/dt (z / r)
+    let r = magnitude(pos);
+    let rdot = (pos[0] * vel[0] + pos[1] * vel[1] + pos[2] * vel[2]) / r;
+    vel[2] * r - pos[2] * rdot > 0.0
+}
+
+/// length of a vector
+pub fn magnitude(v: &[f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+/// angle between two vectors [rad]
+pub fn angle(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    let dot = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    (dot / (magnitude(a) * magnitude(b))).max(-1.0).min(1.0).acos()
+}
+
+/// Returns true if the straight line between the positions `a` and `b` [km]
+/// stays outside the sphere of `radius_km` around the origin.
+pub fn line_of_sight(a: &[f64; 3], b: &[f64; 3], radius_km: f64) -> bool {
+    let d = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let len2 = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+
+    // point of the segment closest to the origin
+    let s = if len2 > 0.0 {
+        (-(a[0] * d[0] + a[1] * d[1] + a[2] * d[2]) / len2).max(0.0).min(1.0)
+    }
+    else {
+        0.0
+    };
+    let closest = [a[0] + s * d[0], a[1] + s * d[1], a[2] + s * d[2]];
+
+    magnitude(&closest) > radius_km
+}
+
+/// `points` points `(lat_deg, lon_deg)` on the circle with angular radius
+/// `radius` [rad] around the given center, longitudes within [-180°, 180°]
+pub fn small_circle(lat_deg: f64, lon_deg: f64, radius: f64, points: usize) -> Vec<(f64, f64)> {
+    let lat = lat_deg.to_radians();
+    let lon = lon_deg.to_radians();
+
+    (0..points).map(|i| {
+        let bearing = 2.0 * PI * i as f64 / points as f64;
+        let lat2 = (lat.sin() * radius.cos() + lat.cos() * radius.sin() * bearing.cos()).asin();
+        let lon2 = lon + (bearing.sin() * radius.sin() * lat.cos()).atan2(radius.cos() - lat.sin() * lat2.sin());
+        let lon2 = (lon2 + 3.0 * PI) % (2.0 * PI) - PI;
+
+        (lat2.to_degrees(), lon2.to_degrees())
+    }).collect()
+}
+
+#[test]
+fn coords_gmst() {
+    use ::clock::from_julian_date;
+
+    // 280.46061837° at J2000.0, Meeus eq. 12.4
+    let gmst = gmst(from_julian_date(2451545.0)).to_degrees();
+    assert!((gmst - 280.46061837).abs() < 1e-6);
+}
+
+#[test]
+fn coords_gmst_before_j2000() {
+    // 13h10m46.3668s on 1987-04-10 0h UT, Meeus example 12.a
+    let gmst = gmst_jd(2446895.5);
+    assert!(gmst >= 0.0 && gmst < 2.0 * PI);
+    assert!((gmst.to_degrees() - 197.693195).abs() < 1e-5);
+}
+
+#[test]
+fn coords_line_of_sight() {
+    let r = EARTH_RADIUS_KM;
+    let a = [r + 500., 0., 0.];
+    // 30° further along the orbit, the chord dips to ~r + 266 km
+    let b = [(r + 500.) * 30f64.to_radians().cos(), (r + 500.) * 30f64.to_radians().sin(), 0.];
+
+    assert!(line_of_sight(&a, &b, r + 200.));
+    assert!(!line_of_sight(&a, &b, r + 300.));
+    assert!(!line_of_sight(&a, &[-r - 500., 0., 0.], r));
+    assert!(line_of_sight(&a, &a, r));
+}
+
+#[test]
+fn coords_geodetic_round_trip() {
+    for &(lat, lon, alt) in [(58.6456, 23.15163, 0.008), (-33.9, -70.7, 2.5), (89.9, 180., 400.), (0., 0., 0.)].iter() {
+        let (lat2, lon2, alt2) = ecef_to_geodetic(&geodetic_to_ecef(lat, lon, alt));
+        assert!((lat2 - lat).abs() < 1e-9 && (alt2 - alt).abs() < 1e-6);
+        assert!(((lon2 - lon + 540.) % 360. - 180.).abs() < 1e-9);
+    }
+
+    // same point in the ECI frame, rotated by the sidereal time
+    let jd = 2457266.5;
+    let ecef = eci_to_ecef(&geodetic_to_eci(58.6456, 23.15163, 0.008, jd), jd);
+    let expected = geodetic_to_ecef(58.6456, 23.15163, 0.008);
+    for i in 0..3 {
+        assert!((ecef[i] - expected[i]).abs() < 1e-6);
+    }
+
+    // straight overhead
+    let zenith = geodetic_to_eci(58.6456, 23.15163, 500., jd);
+    let sez = eci_to_topocentric(58.6456, 23.15163, 0., &zenith, jd);
+    assert!(sez[0].abs() < 1e-6 && sez[1].abs() < 1e-6 && (sez[2] - 500.).abs() < 1e-6);
+}
@@ -22,7 +22,9 @@
  * SOFTWARE.
  */
 
-use libc::{c_void, c_int, c_char, c_double, c_ulong, c_uint};
+#[cfg(not(feature = "pure-rust"))]
+use libc::c_void;
+use libc::{c_int, c_char, c_double, c_ulong, c_uint};
 
 #[repr(C)]
 #[allow(dead_code)]
@@ -55,6 +57,13 @@ pub enum orbit_type_t {
     ORBIT_TYPE_DECAYED
 }
 
+// sat_t.flags
+pub const SGP4_INITIALIZED_FLAG: c_int = 0x000002;
+pub const SDP4_INITIALIZED_FLAG: c_int = 0x000004;
+pub const DEEP_SPACE_EPHEM_FLAG: c_int = 0x000040;
+pub const RESONANCE_FLAG: c_int = 0x000400;
+pub const SYNCHRONOUS_FLAG: c_int = 0x000800;
+
 #[repr(C)]
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -308,6 +317,11 @@ pub struct sat_t {
     pub otype:      orbit_type_t    // orbit type
 }
 
+#[cfg(feature = "pure-rust")]
+pub use sgp4::{get_current_daynum, predict_calc, find_aos, find_los,
+               Get_Next_Tle_Set, select_ephemeris, gtk_sat_data_init_sat};
+
+#[cfg(not(feature = "pure-rust"))]
 #[link(name = "gpredict")]
 extern {
     pub fn get_current_daynum() -> c_double;
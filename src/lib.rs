@@ -27,14 +27,73 @@
 
 #[macro_use]
 extern crate libc;
-extern crate hifitime;
+extern crate chrono;
 extern crate coordinates;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "fetch")]
+extern crate reqwest;
 
 mod sat;
+mod location;
 mod ffipredict;
-mod tle;
+pub mod tle;
 mod predict;
+mod pass;
+mod constellation;
+pub mod schedule;
+mod tracker;
+mod clock;
+pub mod sun;
+pub mod moon;
+pub mod coords;
+#[cfg(feature = "pure-rust")]
+mod sgp4;
+
+pub use self::tle::{OrbitalElements, ParsedTle, Tle, TleError};
+pub use self::location::{look_angles, Location, LocationError};
+pub use self::predict::{DeepSpaceMode, OrbitType, PassIter, Predict, PredictError, RefractionModel, Solver, Visibility};
+pub use self::sat::{AntennaAxis, Sat};
+pub use self::pass::{passes_to_ics, Pass, PassEvent, PassScore};
+pub use self::constellation::{intersat_visible, Constellation};
+pub use self::tracker::{Tracker, TrackerEvent};
+pub use self::clock::{format_local, from_julian_date, parse_time, to_julian_date, Clock, FixedClock, ParseTimeError, SystemClock};
+pub use self::coords::gmst;
+
+// the GRIFEX elements and Tartu observer shared by the tests
+#[cfg(test)]
+fn grifex() -> (Tle, Location) {
+    let tle = Tle {
+        name: "GRIFEX".to_string(),
+        line1: "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993".to_string(),
+        line2: "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889".to_string()
+    };
+    let location = Location { lat_deg: 58.64560, lon_deg: 23.15163, alt_m: 8. };
+
+    (tle, location)
+}
+
+// the SGP4 test case of Spacetrack Report #3 with fixed checksums, the
+// reference gpredict's sgp4sdp4.c is verified against
+#[cfg(test)]
+fn spacetrack_report_3() -> Tle {
+    Tle {
+        name: "SGP4 TEST".to_string(),
+        line1: "1 88888U          80275.98708465  .00073094  13844-3  66816-4 0     9".to_string(),
+        line2: "2 88888  72.8435 115.9689 0086731  52.6988 110.5714 16.05824518   103".to_string(),
+    }
+}
 
-pub use self::tle::Tle;
-pub use self::predict::{Location, Predict};
-pub use self::sat::Sat;
+// (tsince [min], ECI position [km], ECI velocity [km/s]) of
+// `spacetrack_report_3` as published
+#[cfg(test)]
+const SPACETRACK_REPORT_3_EPHEMERIS: [(f64, [f64; 3], [f64; 3]); 5] = [
+    (0.0,    [2328.97048951, -5995.22076416, 1719.97067261], [2.91207230, -0.98341546, -7.09081703]),
+    (360.0,  [2456.10705566, -6071.93853760, 1222.89727783], [2.67938992, -0.44829041, -7.22879231]),
+    (720.0,  [2567.56195068, -6112.50384522, 713.96397400],  [2.44024599, 0.09810869, -7.31995916]),
+    (1080.0, [2663.09078980, -6115.48229980, 196.39640427],  [2.19611958, 0.65241995, -7.36282432]),
+    (1440.0, [2742.55133057, -6079.67144775, -326.38095856], [1.94850229, 1.21106251, -7.35619372]),
+];
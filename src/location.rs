@@ -0,0 +1,196 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::error::Error;
+use std::fmt;
+use chrono::{DateTime, Utc};
+use coordinates::{LLA, ECEF};
+
+use ::clock::to_julian_date;
+use ::coords;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocationError {
+    /// latitude is outside of [-90°, 90°]
+    InvalidLatitude(f64),
+
+    /// longitude is outside of [-180°, 180°]
+    InvalidLongitude(f64),
+
+    /// not a valid Maidenhead grid locator
+    InvalidLocator(String),
+}
+
+impl fmt::Display for LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LocationError::InvalidLatitude(lat) => write!(f, "invalid latitude {}°", lat),
+            LocationError::InvalidLongitude(lon) => write!(f, "invalid longitude {}°", lon),
+            LocationError::InvalidLocator(ref locator) => write!(f, "invalid grid locator {}", locator),
+        }
+    }
+}
+
+impl Error for LocationError {}
+
+/// Observer location
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Location {
+    /// latitude [deg], positive north
+    pub lat_deg:    f64,
+
+    /// longitude [deg], positive east
+    pub lon_deg:    f64,
+
+    /// altitude above sea level [m], negative below sea level
+    pub alt_m:      f64,
+}
+
+impl Location {
+    pub fn new(lat_deg: f64, lon_deg: f64, alt_m: f64) -> Result<Location, LocationError> {
+        if !(lat_deg.abs() <= 90.0) {
+            return Err(LocationError::InvalidLatitude(lat_deg));
+        }
+        if !(lon_deg.abs() <= 180.0) {
+            return Err(LocationError::InvalidLongitude(lon_deg));
+        }
+
+        Ok(Location { lat_deg: lat_deg, lon_deg: lon_deg, alt_m: alt_m })
+    }
+
+    /// Creates a location at the center of a Maidenhead grid square, e.g. "JO21"
+    /// or "KO29hk". Altitude is set to 0 m.
+    pub fn from_grid(locator: &str) -> Result<Location, LocationError> {
+        let bytes = locator.trim().to_ascii_uppercase().into_bytes();
+        if bytes.len() < 2 || bytes.len() > 8 || bytes.len() % 2 != 0 {
+            return Err(LocationError::InvalidLocator(locator.to_string()));
+        }
+
+        let mut lon = -180.0;
+        let mut lat = -90.0;
+        let mut lon_size = 360.0;
+        let mut lat_size = 180.0;
+
+        // field (A-R), square (0-9), subsquare (A-X), extended square (0-9)
+        for (i, pair) in bytes.chunks(2).enumerate() {
+            let (base, divisions) = match i {
+                0 => (b'A', 18),
+                2 => (b'A', 24),
+                _ => (b'0', 10),
+            };
+
+            let x = pair[0].wrapping_sub(base);
+            let y = pair[1].wrapping_sub(base);
+            if x >= divisions || y >= divisions {
+                return Err(LocationError::InvalidLocator(locator.to_string()));
+            }
+
+            lon_size /= divisions as f64;
+            lat_size /= divisions as f64;
+            lon += x as f64 * lon_size;
+            lat += y as f64 * lat_size;
+        }
+
+        Location::new(lat + lat_size / 2.0, lon + lon_size / 2.0, 0.0)
+    }
+
+    /// Creates a location from an earth fixed position [km], e.g. of a
+    /// surveyed station, using the WGS-72 ellipsoid of SGP4.
+    pub fn from_ecef(x_km: f64, y_km: f64, z_km: f64) -> Location {
+        let (lat_deg, lon_deg, alt_km) = coords::ecef_to_geodetic(&[x_km, y_km, z_km]);
+        Location { lat_deg: lat_deg, lon_deg: lon_deg, alt_m: alt_km * 1000.0 }
+    }
+}
+
+impl From<Location> for LLA {
+    fn from(location: Location) -> LLA {
+        LLA {
+            lat_deg: location.lat_deg,
+            lon_deg: location.lon_deg,
+            alt_m:   location.alt_m,
+        }
+    }
+}
+
+impl<'a> From<&'a Location> for LLA {
+    fn from(location: &Location) -> LLA {
+        LLA::from(*location)
+    }
+}
+
+impl From<LLA> for Location {
+    fn from(lla: LLA) -> Location {
+        Location {
+            lat_deg: lla.lat_deg,
+            lon_deg: lla.lon_deg,
+            alt_m:   lla.alt_m,
+        }
+    }
+}
+
+impl From<Location> for ECEF {
+    fn from(location: Location) -> ECEF {
+        LLA::from(location).into()
+    }
+}
+
+/// Topocentric `(az_deg, el_deg, range_km)` of an ECI position [km] seen
+/// from `observer` at time `t`, the same transform used for satellites.
+pub fn look_angles(observer: &Location, target_eci_km: [f64; 3], t: DateTime<Utc>) -> (f64, f64, f64) {
+    coords::look_angles(observer.lat_deg, observer.lon_deg, observer.alt_m / 1000.0,
+                        &target_eci_km, to_julian_date(t))
+}
+
+#[test]
+fn location_validation() {
+    assert!(Location::new(58.6, 23.1, 8.).is_ok());
+    assert_eq!(Location::new(90.1, 0., 0.), Err(LocationError::InvalidLatitude(90.1)));
+    assert_eq!(Location::new(0., -180.1, 0.), Err(LocationError::InvalidLongitude(-180.1)));
+}
+
+#[test]
+fn location_from_grid() {
+    let jo21 = Location::from_grid("JO21").unwrap();
+    assert!((jo21.lat_deg - 51.5).abs() < 1e-9);
+    assert!((jo21.lon_deg - 5.0).abs() < 1e-9);
+
+    let ko29hk = Location::from_grid("ko29hk").unwrap();
+    assert!(ko29hk.lat_deg > 59.0 && ko29hk.lat_deg < 60.0);
+    assert!(ko29hk.lon_deg > 24.0 && ko29hk.lon_deg < 25.0);
+
+    assert!(Location::from_grid("ZZ21").is_err());
+    assert!(Location::from_grid("JO2").is_err());
+}
+
+#[test]
+fn location_from_ecef() {
+    let ecef = coords::geodetic_to_ecef(58.64560, 23.15163, -0.4);
+    let location = Location::from_ecef(ecef[0], ecef[1], ecef[2]);
+
+    assert!((location.lat_deg - 58.64560).abs() < 1e-9);
+    assert!((location.lon_deg - 23.15163).abs() < 1e-9);
+    assert!((location.alt_m + 400.).abs() < 1e-6);
+}
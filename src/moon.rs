@@ -0,0 +1,105 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::f64::consts::PI;
+use chrono::{DateTime, Utc};
+
+use ::clock::to_julian_date;
+use ::location::{self, Location};
+
+/// arc seconds per radian
+const ARCSEC_PER_RAD: f64 = 3600.0 * 180.0 / PI;
+
+/// Moon position in ECI coordinates [km] at the given time.
+///
+/// Uses the main periodic terms of the lunar theory (Montenbruck & Pfleger,
+/// Astronomy on the Personal Computer, MiniMoon, and Meeus, Astronomical
+/// Algorithms, chapter 47 for the distance), good to a few arc minutes.
+pub fn position(t: DateTime<Utc>) -> [f64; 3] {
+    position_jd(to_julian_date(t))
+}
+
+pub(crate) fn position_jd(jd: f64) -> [f64; 3] {
+    let t = (jd - 2451545.0) / 36525.0;
+    let rev = |a: f64| 2.0 * PI * a.fract();
+
+    // mean longitude [rev], mean anomalies of moon and sun, mean elongation
+    // and argument of latitude [rad]
+    let l0 = (0.606433 + 1336.855225 * t).fract();
+    let l = rev(0.374897 + 1325.552410 * t);
+    let ls = rev(0.993133 + 99.997361 * t);
+    let d = rev(0.827361 + 1236.853086 * t);
+    let f = rev(0.259086 + 1342.227825 * t);
+
+    // perturbations of the longitude [arcsec]
+    let dl = 22640.0 * l.sin() - 4586.0 * (l - 2.0 * d).sin() + 2370.0 * (2.0 * d).sin()
+             + 769.0 * (2.0 * l).sin() - 668.0 * ls.sin() - 412.0 * (2.0 * f).sin()
+             - 212.0 * (2.0 * l - 2.0 * d).sin() - 206.0 * (l + ls - 2.0 * d).sin()
+             + 192.0 * (l + 2.0 * d).sin() - 165.0 * (ls - 2.0 * d).sin() - 125.0 * d.sin()
+             - 110.0 * (l + ls).sin() + 148.0 * (l - ls).sin() - 55.0 * (2.0 * f - 2.0 * d).sin();
+
+    let s = f + (dl + 412.0 * (2.0 * f).sin() + 541.0 * ls.sin()) / ARCSEC_PER_RAD;
+    let h = f - 2.0 * d;
+    let n = -526.0 * h.sin() + 44.0 * (l + h).sin() - 31.0 * (h - l).sin() - 23.0 * (ls + h).sin()
+            + 11.0 * (h - ls).sin() - 25.0 * (f - 2.0 * l).sin() + 21.0 * (f - l).sin();
+
+    // ecliptic longitude, latitude [rad] and distance [km]
+    let lon = rev(l0 + dl / 1296.0e3);
+    let lat = (18520.0 * s.sin() + n) / ARCSEC_PER_RAD;
+    let r = 385000.56 - 20905.36 * l.cos() - 3699.11 * (2.0 * d - l).cos() - 2955.97 * (2.0 * d).cos()
+            - 569.93 * (2.0 * l).cos() + 48.89 * ls.cos() - 3.15 * (2.0 * f).cos()
+            + 246.16 * (2.0 * d - 2.0 * l).cos() - 152.14 * (2.0 * d - ls - l).cos()
+            - 170.73 * (2.0 * d + l).cos() - 204.59 * (2.0 * d - ls).cos();
+
+    let eps = (23.43929111 - 0.0130042 * t).to_radians();
+    let (sin_lon, cos_lon) = lon.sin_cos();
+    let (sin_lat, cos_lat) = lat.sin_cos();
+
+    [r * cos_lat * cos_lon,
+     r * (eps.cos() * cos_lat * sin_lon - eps.sin() * sin_lat),
+     r * (eps.sin() * cos_lat * sin_lon + eps.cos() * sin_lat)]
+}
+
+/// Topocentric `(az_deg, el_deg)` of the moon seen from `observer`, e.g. as
+/// a known target for calibrating a rotator.
+pub fn look_angles(observer: &Location, t: DateTime<Utc>) -> (f64, f64) {
+    let (az, el, _) = location::look_angles(observer, position(t), t);
+    (az, el)
+}
+
+#[test]
+fn moon_position() {
+    use ::coords;
+
+    // 1992 April 12 0h TD, Meeus example 47.a: geocentric RA 134.688°,
+    // dec 13.768° and distance 368410 km
+    let moon = position_jd(2448724.5);
+    let r = coords::magnitude(&moon);
+    let ra = moon[1].atan2(moon[0]).to_degrees();
+    let dec = (moon[2] / r).asin().to_degrees();
+
+    assert!((r - 368410.0).abs() < 100.0, "{}", r);
+    assert!((ra - 134.688).abs() < 0.05, "{}", ra);
+    assert!((dec - 13.768).abs() < 0.05, "{}", dec);
+}
@@ -0,0 +1,321 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use chrono::{DateTime, Utc};
+
+use ::predict::{Predict, CROSSING_PRECISION_DAYS};
+use ::clock::{from_julian_date, to_julian_date};
+
+/// elevation sampling step for `PassScore::ElevationIntegral` [sec]
+const SCORE_STEP_SECS: f64 = 10.0;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pass {
+    /// AOS
+    pub aos:                DateTime<Utc>,
+
+    /// LOS
+    pub los:                DateTime<Utc>,
+
+    /// time of the maximum elevation
+    pub culmination:        DateTime<Utc>,
+
+    /// maximum elevation during the pass [deg]
+    pub max_elevation_deg:  f64,
+
+    /// azimuth at AOS [deg]
+    pub aos_azimuth_deg:    f64,
+
+    /// azimuth at LOS [deg]
+    pub los_azimuth_deg:    f64,
+
+    /// pass duration [sec]
+    pub duration_secs:      i64,
+
+    /// satellite is moving north at the culmination
+    pub ascending:          bool,
+}
+
+/// longest iCalendar content line before it is folded [octets]
+const ICS_LINE_OCTETS: usize = 75;
+
+/// How `Pass::quality_score` rates a pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassScore {
+    /// integral of the elevation over the pass [deg sec]
+    ElevationIntegral,
+
+    /// maximum elevation times duration [deg sec], no propagation needed
+    PeakTimesDuration,
+}
+
+impl Pass {
+    /// Single number rating the pass for a link budget, larger is better,
+    /// e.g. for sorting `upcoming_passes`.
+    ///
+    /// `predict` must be the one that found this pass, it is only used for
+    /// `PassScore::ElevationIntegral`.
+    pub fn quality_score(&self, predict: &mut Predict, score: PassScore) -> f64 {
+        match score {
+            PassScore::PeakTimesDuration => self.max_elevation_deg * self.duration_secs as f64,
+            PassScore::ElevationIntegral => {
+                let aos = to_julian_date(self.aos);
+                let los = to_julian_date(self.los);
+                let step = SCORE_STEP_SECS / 86400.0;
+                let el = |predict: &mut Predict, jd: f64| predict.observe_at(from_julian_date(jd)).el_deg.max(0.0);
+
+                // trapezoidal rule
+                let mut integral = 0.0;
+                let mut t = aos;
+                let mut last = el(predict, t);
+                while t < los {
+                    let next = (t + step).min(los);
+                    let current = el(predict, next);
+                    integral += (last + current) / 2.0 * (next - t) * 86400.0;
+                    last = current;
+                    t = next;
+                }
+
+                integral
+            }
+        }
+    }
+
+    /// Returns the part `(start, end)` of the pass where the elevation is at
+    /// least `elevation_deg`, e.g. for an antenna which only works above 20°.
+    ///
+    /// `None` if the pass never gets that high, `(aos, los)` if it stays above
+    /// throughout. `predict` must be the one that found this pass.
+    pub fn window_above(&self, predict: &mut Predict, elevation_deg: f64) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.max_elevation_deg < elevation_deg {
+            return None;
+        }
+
+        let aos = to_julian_date(self.aos);
+        let los = to_julian_date(self.los);
+        let culmination = to_julian_date(self.culmination);
+        let mut above = |jd: f64| predict.observe_at(from_julian_date(jd)).el_deg >= elevation_deg;
+
+        // the elevation rises until the culmination and falls after it
+        let mut start = if above(aos) { aos } else { bisect(&mut above, aos, culmination) };
+        let mut end = if above(los) { los } else { bisect(&mut above, los, culmination) };
+
+        // AOS/LOS are only that precise themselves, e.g. for a threshold of 0°
+        if start - aos <= 2.0 * CROSSING_PRECISION_DAYS {
+            start = aos;
+        }
+        if los - end <= 2.0 * CROSSING_PRECISION_DAYS {
+            end = los;
+        }
+
+        Some((from_julian_date(start), from_julian_date(end)))
+    }
+
+    /// iCalendar VEVENT from AOS to LOS, summarized with `sat_name` and the
+    /// maximum elevation, see `passes_to_ics` for a complete calendar.
+    ///
+    /// DTSTAMP is set to the AOS so the output only depends on the pass.
+    pub fn to_ics_event(&self, sat_name: &str) -> String {
+        self.ics_event(Some(sat_name))
+    }
+
+    // VEVENT lines, the UID and SUMMARY only name the satellite if it is known
+    fn ics_event(&self, sat_name: Option<&str>) -> String {
+        let aos = ics_time(self.aos);
+        let (uid, summary) = match sat_name {
+            Some(name) => (format!("{}-{}", aos, name.replace(|c: char| !c.is_ascii_alphanumeric(), "")),
+                           format!("{} pass", ics_text(name))),
+            None => (aos.clone(), "Pass".to_string()),
+        };
+        let lines = [
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@gpredict", uid),
+            format!("DTSTAMP:{}", aos),
+            format!("DTSTART:{}", aos),
+            format!("DTEND:{}", ics_time(self.los)),
+            format!("SUMMARY:{}\\, max el {:.1}°", summary, self.max_elevation_deg),
+            format!("DESCRIPTION:AOS az {:.1}°\\nculmination {}\\nLOS az {:.1}°",
+                    self.aos_azimuth_deg, self.culmination.format("%Y-%m-%dT%H:%M:%SZ"), self.los_azimuth_deg),
+            "END:VEVENT".to_string(),
+        ];
+
+        lines.iter().map(|line| fold_ics_line(line)).collect()
+    }
+}
+
+/// iCalendar with one VEVENT per pass, e.g. from `Predict::upcoming_passes`.
+///
+/// `Pass` does not know its satellite, so the events are summarized with the
+/// maximum elevation only, use `Pass::to_ics_event` for named events.
+pub fn passes_to_ics(passes: &[Pass]) -> String {
+    let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//rust-gpredict//passes//EN\r\n");
+    for pass in passes {
+        ics.push_str(&pass.ics_event(None));
+    }
+    ics.push_str("END:VCALENDAR\r\n");
+
+    ics
+}
+
+// UTC date-time in the iCalendar basic format, e.g. "20150901T030000Z"
+fn ics_time(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// escapes the characters with a meaning in iCalendar TEXT values
+fn ics_text(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+// terminates a content line with CRLF, continuation lines start with a space
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > ICS_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+
+    folded
+}
+
+// boundary between `below` and `above` for `above(below) == false`,
+// `above(above) == true`, in either order
+fn bisect<F: FnMut(f64) -> bool>(f: &mut F, mut below: f64, mut above: f64) -> f64 {
+    while (above - below).abs() > CROSSING_PRECISION_DAYS {
+        let t = (below + above) / 2.0;
+        if f(t) {
+            above = t;
+        }
+        else {
+            below = t;
+        }
+    }
+
+    above
+}
+
+/// AOS, culmination or LOS of a pass, see `Predict::events_between`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PassEvent {
+    /// satellite rises above the horizon
+    Aos {
+        time:       DateTime<Utc>,
+        az_deg:     f64,
+    },
+
+    /// satellite sets below the horizon
+    Los {
+        time:       DateTime<Utc>,
+        az_deg:     f64,
+    },
+
+    /// highest elevation of the pass
+    Culmination {
+        time:       DateTime<Utc>,
+        el_deg:     f64,
+        az_deg:     f64,
+    },
+}
+
+impl PassEvent {
+    /// Time of the event, convenient for merging the events of several satellites.
+    pub fn time(&self) -> DateTime<Utc> {
+        match *self {
+            PassEvent::Aos { time, .. } => time,
+            PassEvent::Los { time, .. } => time,
+            PassEvent::Culmination { time, .. } => time,
+        }
+    }
+}
+
+#[test]
+fn pass_quality_score() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let pass = predict.next_pass(from_julian_date(2457266.5)).unwrap();
+
+    let integral = pass.quality_score(&mut predict, PassScore::ElevationIntegral);
+    let heuristic = pass.quality_score(&mut predict, PassScore::PeakTimesDuration);
+    assert!(integral > 0. && integral < heuristic);
+}
+
+#[test]
+fn pass_window_above() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let pass = predict.next_pass(from_julian_date(2457266.5)).unwrap();
+
+    assert!(pass.window_above(&mut predict, pass.max_elevation_deg + 1.).is_none());
+
+    let (start, end) = pass.window_above(&mut predict, 0.).unwrap();
+    assert_eq!((to_julian_date(start), to_julian_date(end)), (to_julian_date(pass.aos), to_julian_date(pass.los)));
+
+    let threshold = pass.max_elevation_deg / 2.;
+    let (start, end) = pass.window_above(&mut predict, threshold).unwrap();
+    assert!(to_julian_date(start) > to_julian_date(pass.aos) && to_julian_date(end) < to_julian_date(pass.los));
+    assert!((predict.observe_at(start).el_deg - threshold).abs() < 0.01);
+    assert!((predict.observe_at(end).el_deg - threshold).abs() < 0.01);
+}
+
+#[test]
+fn pass_to_ics() {
+    let pass = Pass {
+        aos:                from_julian_date(2457266.75),
+        los:                from_julian_date(2457266.75625),
+        culmination:        from_julian_date(2457266.75) + chrono::Duration::seconds(270),
+        max_elevation_deg:  26.94,
+        aos_azimuth_deg:    37.6,
+        los_azimuth_deg:    124.0,
+        duration_secs:      540,
+        ascending:          false,
+    };
+
+    let event = pass.to_ics_event("GRIFEX");
+    assert!(event.starts_with("BEGIN:VEVENT\r\nUID:20150901T060000Z-GRIFEX@gpredict\r\n"));
+    assert!(event.contains("\r\nDTSTART:20150901T060000Z\r\nDTEND:20150901T060900Z\r\n"));
+    assert!(event.contains("\r\nSUMMARY:GRIFEX pass\\, max el 26.9°\r\n"));
+    assert!(event.ends_with("END:VEVENT\r\n"));
+    assert!(event.contains("\r\nDESCRIPTION:AOS az 37.6°\\nculmination 2015-09-01T06:04:30Z\\nLOS az 124.0°\r\n"));
+    assert!(event.split("\r\n").all(|line| line.len() <= ICS_LINE_OCTETS));
+    assert!(pass.to_ics_event("ISS (ZARYA); crew").contains("SUMMARY:ISS (ZARYA)\\; crew pass"));
+
+    let ics = passes_to_ics(&[pass.clone(), pass.clone()]);
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    assert!(ics.contains("\r\nUID:20150901T060000Z@gpredict\r\n"));
+    assert!(ics.contains("\r\nSUMMARY:Pass\\, max el 26.9°\r\n"));
+
+    // long content lines are folded
+    let event = pass.to_ics_event(&"ÄÖ".repeat(40));
+    assert!(event.split("\r\n").all(|line| line.len() <= ICS_LINE_OCTETS));
+    assert!(event.contains("\r\n "));
+}
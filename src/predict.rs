@@ -22,15 +22,169 @@
  * SOFTWARE.
  */
 
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::default::Default;
-use hifitime::Epoch;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::ffi::CString;
+use std::fmt;
+use std::iter;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
 use coordinates::LLA;
 
 use ::ffipredict;
-use ::tle;
+use ::tle::{self, ParsedTle, TleError};
+use ::location::Location;
 use ::sat::Sat;
+use ::pass::{Pass, PassEvent};
+use ::sun;
+use ::coords::{self, EarthModel};
+use ::clock::{from_julian_date, to_julian_date, Clock, SystemClock};
 
-pub type Location = LLA;
+/// elevation sampling step used for finding the culmination of a pass [days]
+const PASS_STEP_DAYS: f64 = 10.0 / 86400.0;
+
+/// how far ahead pass searches look before giving up [days]
+const PASS_SEARCH_HORIZON_DAYS: f64 = 14.0;
+
+/// precision of the rise/set search against a custom AOS elevation [days]
+pub(crate) const CROSSING_PRECISION_DAYS: f64 = 0.1 / 86400.0;
+
+/// how far before the geometric AOS and after the geometric LOS the
+/// refracted horizon crossings are searched for [days]
+const REFRACTION_SEARCH_DAYS: f64 = 600.0 / 86400.0;
+
+/// how far around the AOS/LOS found by gpredict the bisection solver looks
+/// for the horizon crossing [days]
+const SOLVER_SEARCH_DAYS: f64 = 60.0 / 86400.0;
+
+/// sun elevation below which the observer is considered to be in darkness [deg]
+const TWILIGHT_ELEVATION_DEG: f64 = -6.0;
+
+/// Orbit type as classified by gpredict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitType {
+    Unknown,
+    /// low earth orbit, up to 1200 km
+    Leo,
+    /// intermediate circular orbit, up to 1400 km
+    Ico,
+    /// geostationary
+    Geo,
+    /// geosynchronous
+    Gso,
+    Molniya,
+    Tundra,
+    Polar,
+    SunSynchronous,
+    Decayed,
+}
+
+/// Resonance corrections applied by the SDP4 deep space model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeepSpaceMode {
+    /// no resonance terms
+    NonResonant,
+    /// 12 hour resonance, e.g. Molniya and GPS orbits
+    HalfDayResonance,
+    /// 24 hour synchronous resonance, e.g. geostationary orbits
+    Synchronous,
+}
+
+/// Whether and how a satellite can be seen from the observer location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// geostationary satellite above the horizon
+    AlwaysVisible,
+    /// geostationary below the horizon, decayed, or the orbit never reaches
+    /// the observer's latitude
+    NeverVisible,
+    /// rises and sets
+    Periodic,
+}
+
+/// Atmospheric refraction applied to the elevation for AOS/LOS detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefractionModel {
+    /// geometric elevation
+    None,
+    /// ITU-R P.834 radio refraction, about 0.76° at the horizon
+    Radio,
+    /// Sæmundsson's formula for visible light, about 0.48° at the horizon
+    Optical,
+}
+
+impl RefractionModel {
+    /// Amount [deg] the atmosphere lifts a target at the geometric
+    /// `elevation_deg` seen from `alt_km` above sea level.
+    pub fn correction_deg(&self, elevation_deg: f64, alt_km: f64) -> f64 {
+        let el = elevation_deg.max(0.0);
+
+        match *self {
+            RefractionModel::None => 0.0,
+            RefractionModel::Radio => {
+                let h = alt_km.max(0.0);
+                1.0 / (1.314 + 0.6437 * el + 0.02869 * el * el
+                       + h * (0.2305 + 0.09428 * el + 0.01096 * el * el) + 0.008583 * h * h)
+            }
+            RefractionModel::Optical => {
+                let arcmin = 1.02 / (el + 10.3 / (el + 5.11)).to_radians().tan();
+                (arcmin / 60.0).max(0.0)
+            }
+        }
+    }
+}
+
+/// How AOS and LOS at the horizon are found, see `Predict::set_pass_solver`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solver {
+    /// the gpredict search, which stops within a few thousandths of a degree
+    /// of the horizon
+    Gpredict,
+    /// the gpredict result refined by bisection to 0.1 s
+    Bisection,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PredictError {
+    /// TLE could not be parsed, carries the name of the offending `Tle` field
+    TleParse(&'static str),
+
+    /// TLE lines have the wrong length, line number or checksum
+    InvalidTle(TleError),
+
+    /// propagation produced non-finite values or an altitude below the
+    /// surface, e.g. for decayed satellites
+    PropagationDiverged,
+
+    /// the propagator was not initialized for the satellite
+    NotInitialized,
+
+    /// deep space (SDP4) satellite, the `pure-rust` propagator only
+    /// implements SGP4
+    DeepSpaceUnsupported,
+
+    /// the requested time is further from the TLE epoch than the limit set
+    /// by `Predict::set_max_tle_age`, carries the TLE age at that time
+    StaleTle(Duration),
+}
+
+impl fmt::Display for PredictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PredictError::TleParse(field) => write!(f, "error in TLE parsing: invalid {}", field),
+            PredictError::InvalidTle(ref e) => write!(f, "invalid TLE: {}", e),
+            PredictError::PropagationDiverged => write!(f, "propagation diverged, the TLE may be decayed"),
+            PredictError::NotInitialized => write!(f, "satellite is not initialized"),
+            PredictError::DeepSpaceUnsupported => write!(f, "deep space satellites are not supported by the pure-rust propagator"),
+            PredictError::StaleTle(age) => write!(f, "TLE is stale, {:.1} days from its epoch", age.as_secs_f64() / 86400.0),
+        }
+    }
+}
+
+impl Error for PredictError {}
 
 #[derive(Debug)]
 pub struct Predict {
@@ -38,12 +192,105 @@ pub struct Predict {
 
     p_sat: ffipredict::sat_t,
     p_qth: ffipredict::qth_t,
+
+    // owned strings the FFI string pointers of p_sat and p_qth point to
+    sat_name: CString,
+    _empty: CString,
+
+    // how far find_aos/find_los look ahead [days]
+    search_window_days: f64,
+
+    // elevation defining AOS/LOS [deg]
+    aos_elevation_deg: f64,
+
+    // (azimuth, minimum elevation) points sorted by azimuth [deg]
+    horizon_mask: Vec<(f64, f64)>,
+
+    // lifts the elevation compared against the horizon
+    refraction: RefractionModel,
+
+    // margin around the horizon for Tracker AOS/LOS events [deg]
+    event_hysteresis_deg: f64,
+
+    // update_at refuses to predict further than this from the TLE epoch
+    max_tle_age: Option<Duration>,
+
+    // refinement of the horizon crossings
+    solver: Solver,
+
+    // time source for update_now
+    clock: Box<dyn Clock + Send>,
+
+    // ellipsoid handed to Sat for the Rust side geometry
+    earth: EarthModel,
+
+    // see Tle::is_likely_decayed
+    likely_decayed: bool,
+
+    // observe_at snapshots keyed by milliseconds since the Julian day count
+    // starts, most recently used first, disabled with capacity 0
+    cache: VecDeque<(i64, Sat)>,
+    cache_capacity: usize,
+}
+
+/// Iterator over upcoming passes, see `Predict::pass_iter`.
+#[derive(Debug)]
+pub struct PassIter<'a> {
+    predict: &'a mut Predict,
+
+    // search cursor [julian days]
+    t: f64,
+
+    // end of the search if no further pass is found [julian days]
+    give_up: f64,
+
+    min_elevation_deg: f64,
 }
 
+impl<'a> Iterator for PassIter<'a> {
+    type Item = Pass;
+
+    fn next(&mut self) -> Option<Pass> {
+        while self.t < self.give_up {
+            match self.predict.next_pass(from_julian_date(self.t)) {
+                Some(pass) => {
+                    // continue the search shortly after LOS
+                    self.t = to_julian_date(pass.los) + 60.0 / 86400.0;
+                    if pass.max_elevation_deg >= self.min_elevation_deg {
+                        self.give_up = self.t + PASS_SEARCH_HORIZON_DAYS;
+                        return Some(pass);
+                    }
+                }
+                // next_pass only looks one search window ahead
+                None => self.t += self.predict.search_window_days,
+            }
+        }
+
+        None
+    }
+}
+
+// Predict is not Send by default because of the raw string pointers in p_sat
+// and p_qth. They only point into the heap buffers of sat_name and _empty,
+// which are owned by the same Predict, never modified and move along with
+// it. libgpredict keeps all propagation state inside sat_t, so a Predict can
+// be used from any thread as long as it is not shared, which is why it is
+// not Sync. The clock is required to be Send.
+unsafe impl Send for Predict {}
+
 impl Predict {
 
-    pub fn new<T: Into<LLA>>(tle: &tle::Tle, location: T) -> Predict {
-        let tle_t = tle::create_tle_t(tle).unwrap();
+    pub fn new<T: Into<LLA>>(tle: &tle::Tle, location: T) -> Result<Predict, PredictError> {
+        Ok(Predict::from_parsed(&ParsedTle::new(tle)?, location))
+    }
+
+    /// Creates a `Predict` from elements which were already parsed, e.g.
+    /// when tracking one satellite from many locations.
+    pub fn from_parsed<T: Into<LLA>>(tle: &ParsedTle, location: T) -> Predict {
+        let tle_t = tle.tle_t.clone();
+        let likely_decayed = tle::is_likely_decayed(&tle_t);
+        let sat_name = tle.name.clone();
+        let empty = CString::default();
         let location_lla: LLA = location.into();
 
         let sgps: ffipredict::sgpsdp_static_t = Default::default();
@@ -53,9 +300,9 @@ impl Predict {
         let vel: ffipredict::vector_t = Default::default();
 
         let mut sat_t = ffipredict::sat_t{
-            name: b"placeholder\0".as_ptr() as *const _,
-            nickname: b"placeholder\0".as_ptr() as *const _,
-            website: b"placeholder\0".as_ptr() as *const _,
+            name: sat_name.as_ptr(),
+            nickname: sat_name.as_ptr(),
+            website: empty.as_ptr(),
             tle: tle_t,
             flags: 0,
             sgps: sgps,
@@ -89,71 +336,1592 @@ impl Predict {
 
         let sat: Sat = Default::default();
         let mut qth = ffipredict::qth_t {
-            name: b"placeholder\0".as_ptr() as *const _,
-            loc: b"placeholder\0".as_ptr() as *const _,
-            desc: b"placeholder\0".as_ptr() as *const _,
+            name: empty.as_ptr(),
+            loc: empty.as_ptr(),
+            desc: empty.as_ptr(),
             lat: location_lla.lat_deg,
             lon: location_lla.lon_deg,
-            alt: location_lla.alt_m as i32,
-            qra: b"placeholder\0".as_ptr() as *const _,
-            wx: b"placeholder\0".as_ptr() as *const _,
+            alt: qth_altitude(location_lla.alt_m),
+            qra: empty.as_ptr(),
+            wx: empty.as_ptr(),
         };
 
         unsafe {ffipredict::select_ephemeris(&mut sat_t)};
         unsafe {ffipredict::gtk_sat_data_init_sat(&mut sat_t, &mut qth)};
 
-        Predict{sat: sat, p_sat: sat_t, p_qth: qth}
+        // the CString buffers live on the heap, so the pointers stay valid when
+        // Predict is moved
+        Predict{sat: sat, p_sat: sat_t, p_qth: qth, sat_name: sat_name, _empty: empty,
+                search_window_days: 1.0, aos_elevation_deg: 0.0, horizon_mask: Vec::new(),
+                refraction: RefractionModel::None, event_hysteresis_deg: 0.0, max_tle_age: None,
+                solver: Solver::Gpredict, clock: Box::new(SystemClock), earth: EarthModel::default(),
+                likely_decayed: likely_decayed, cache: VecDeque::new(), cache_capacity: 0}
+    }
+
+    /// Replaces the elements with a fresh TLE, keeping the observer location.
+    ///
+    /// `self.sat` still describes the previous elements until the next
+    /// update, use `reset_sat` when swapping to a different satellite.
+    pub fn update_tle(&mut self, tle: &tle::Tle) -> Result<(), PredictError> {
+        let parsed = ParsedTle::new(tle)?;
+        self.likely_decayed = tle::is_likely_decayed(&parsed.tle_t);
+        self.p_sat.tle = parsed.tle_t;
+        self.sat_name = parsed.name;
+        self.p_sat.name = self.sat_name.as_ptr();
+        self.p_sat.nickname = self.sat_name.as_ptr();
+
+        // clear the SGP4/SDP4 initialized flags, otherwise the propagator keeps
+        // using the previous elements
+        self.p_sat.flags = 0;
+
+        unsafe {ffipredict::select_ephemeris(&mut self.p_sat)};
+        unsafe {ffipredict::gtk_sat_data_init_sat(&mut self.p_sat, &mut self.p_qth)};
+        self.cache.clear();
+
+        Ok(())
+    }
+
+    /// Returns true if the elements most likely describe a reentered object,
+    /// see `Tle::is_likely_decayed`. Predictions for these are nonsense, so
+    /// check this after `new` or `update_tle` when using feeds which still
+    /// list decayed objects.
+    pub fn is_likely_decayed(&self) -> bool {
+        self.likely_decayed
+    }
+
+    /// Zeros `self.sat` and clears its AOS/LOS, e.g. so a UI does not show
+    /// the previous satellite after `update_tle`.
+    pub fn reset_sat(&mut self) {
+        self.sat = Sat {
+            earth: self.earth,
+            ..Default::default()
+        };
+    }
+
+    /// Orbit type as determined by gpredict when the satellite was initialized.
+    pub fn orbit_type(&self) -> OrbitType {
+        use ffipredict::orbit_type_t::*;
+
+        match self.p_sat.otype {
+            ORBIT_TYPE_UNKNOWN => OrbitType::Unknown,
+            ORBIT_TYPE_LEO => OrbitType::Leo,
+            ORBIT_TYPE_ICO => OrbitType::Ico,
+            ORBIT_TYPE_GEO => OrbitType::Geo,
+            ORBIT_TYPE_GSO => OrbitType::Gso,
+            ORBIT_TYPE_MOLNIYA => OrbitType::Molniya,
+            ORBIT_TYPE_TUNDRA => OrbitType::Tundra,
+            ORBIT_TYPE_POLAR => OrbitType::Polar,
+            ORBIT_TYPE_SUNSYNC => OrbitType::SunSynchronous,
+            ORBIT_TYPE_DECAYED => OrbitType::Decayed,
+        }
+    }
+
+    /// Returns true if the deep space SDP4 model is used instead of SGP4,
+    /// which is the case for orbital periods of 225 minutes or more.
+    pub fn is_deep_space(&self) -> bool {
+        self.p_sat.flags & ffipredict::DEEP_SPACE_EPHEM_FLAG != 0
+    }
+
+    /// Revolution number at `t` as gpredict computes `orbit_nr`, counted from
+    /// the revolution number at epoch of the TLE.
+    ///
+    /// gpredict counts mean anomaly revolutions, so the number increments at
+    /// perigee and not at the ascending node like the NORAD revolution
+    /// number. It is one less than the TLE revolution number at epoch until
+    /// the first perigee after it.
+    pub fn revolution_at(&mut self, t: DateTime<Utc>) -> u64 {
+        self.calc(to_julian_date(t));
+        self.p_sat.orbit as u64
+    }
+
+    /// Time between the TLE epoch and `t`, in either direction.
+    ///
+    /// Accuracy degrades with age, element sets older than a few days
+    /// should be refreshed for precise pointing.
+    pub fn tle_age(&self, t: DateTime<Utc>) -> Duration {
+        let secs = (to_julian_date(t) - self.p_sat.jul_epoch).abs() * 86400.0;
+        Duration::from_secs_f64(secs)
+    }
+
+    /// Makes `update_at` and `update_now` fail with `PredictError::StaleTle`
+    /// when the TLE epoch is more than `max_age` away from the requested time,
+    /// `None` (the default) disables the check.
+    pub fn set_max_tle_age(&mut self, max_age: Option<Duration>) {
+        self.max_tle_age = max_age;
+    }
+
+    /// Returns true if `tle_age(t)` exceeds the limit set by `set_max_tle_age`,
+    /// always false without a limit.
+    ///
+    /// `observe_at` does not check the age, its callers can refuse to act on
+    /// stale data with this.
+    pub fn tle_is_stale(&self, t: DateTime<Utc>) -> bool {
+        self.max_tle_age.is_some_and(|max_age| self.tle_age(t) > max_age)
+    }
+
+    /// Resonance mode of the deep space model, `None` for satellites
+    /// propagated with SGP4 or if SDP4 has not been initialized.
+    pub fn deep_space_mode(&self) -> Option<DeepSpaceMode> {
+        let flags = self.p_sat.flags;
+
+        if flags & ffipredict::DEEP_SPACE_EPHEM_FLAG == 0 || flags & ffipredict::SDP4_INITIALIZED_FLAG == 0 {
+            None
+        }
+        else if flags & ffipredict::RESONANCE_FLAG == 0 {
+            Some(DeepSpaceMode::NonResonant)
+        }
+        else if flags & ffipredict::SYNCHRONOUS_FLAG != 0 {
+            Some(DeepSpaceMode::Synchronous)
+        }
+        else {
+            Some(DeepSpaceMode::HalfDayResonance)
+        }
+    }
+
+    /// Orbital period [sec] from the TLE mean motion.
+    pub fn orbital_period_secs(&self) -> f64 {
+        86400.0 / self.p_sat.meanmo
+    }
+
+    /// Nodal period [sec], the time between two ascending node crossings.
+    ///
+    /// Differs from `orbital_period_secs` by the secular J2 drift of the
+    /// mean anomaly and argument of perigee, computed as in SGP4.
+    pub fn nodal_period_secs(&self) -> f64 {
+        // select_ephemeris has converted xno to rad/min and angles to rad
+        let tle = &self.p_sat.tle;
+
+        // recover the original mean motion and semi major axis
+        let a1 = (coords::XKE / tle.xno).powf(2.0 / 3.0);
+        let theta2 = tle.xincl.cos() * tle.xincl.cos();
+        let x3thm1 = 3.0 * theta2 - 1.0;
+        let betao2 = 1.0 - tle.eo * tle.eo;
+        let betao = betao2.sqrt();
+        let del1 = 1.5 * coords::CK2 * x3thm1 / (a1 * a1 * betao * betao2);
+        let ao = a1 * (1.0 - del1 * (1.0 / 3.0 + del1 * (1.0 + 134.0 / 81.0 * del1)));
+        let delo = 1.5 * coords::CK2 * x3thm1 / (ao * ao * betao * betao2);
+        let xnodp = tle.xno / (1.0 + delo);
+        let aodp = ao / (1.0 - delo);
+
+        let temp1 = 3.0 * coords::CK2 * xnodp / (aodp * aodp * betao2 * betao2);
+        let xmdot = xnodp + 0.5 * temp1 * betao * x3thm1;
+        let omgdot = -0.5 * temp1 * (1.0 - 5.0 * theta2);
+
+        2.0 * PI / (xmdot + omgdot) * 60.0
+    }
+
+    /// Upper bound of the elevation [deg] the satellite can reach from the
+    /// observer, without searching for passes.
+    ///
+    /// Assumes the satellite at apogee passes as close to the observer as its
+    /// inclination allows, 90° if it can pass overhead and negative if it
+    /// never rises. -90° if the mean motion is unknown.
+    pub fn theoretical_max_elevation(&self) -> f64 {
+        if self.p_sat.meanmo == 0.0 {
+            return -90.0;
+        }
+
+        // xincl is already in rad by select_ephemeris
+        let tle = &self.p_sat.tle;
+        let max_lat = if tle.xincl >= PI / 2.0 { PI - tle.xincl } else { tle.xincl };
+
+        let sma = 331.25 * ((1440.0 / self.p_sat.meanmo).ln() * (2.0 / 3.0)).exp();
+        let apogee_radius = sma * (1.0 + tle.eo);
+
+        // earth central angle between the observer and the closest sub-satellite point
+        let angle = (self.p_qth.lat.to_radians().abs() - max_lat).max(0.0);
+        (angle.cos() - coords::EARTH_RADIUS_KM / apogee_radius).atan2(angle.sin()).to_degrees()
+    }
+
+    /// Tells apart satellites which never set from those which never rise,
+    /// both of which have no AOS/LOS.
+    pub fn visibility_state(&self) -> Visibility {
+        use ffipredict::orbit_type_t::*;
+
+        match self.p_sat.otype {
+            ORBIT_TYPE_DECAYED => Visibility::NeverVisible,
+            ORBIT_TYPE_GEO if self.clearance() > 0.0 => Visibility::AlwaysVisible,
+            ORBIT_TYPE_GEO => Visibility::NeverVisible,
+            _ if !can_rise(&self.p_sat, &self.p_qth) => Visibility::NeverVisible,
+            _ => Visibility::Periodic,
+        }
+    }
+
+    /// Sets how far ahead AOS and LOS are searched for, default is one day.
+    ///
+    /// This is the `maxdt` limit of the gpredict AOS/LOS search, the step size
+    /// itself is adapted to the satellite elevation by gpredict. Satellites
+    /// whose next AOS or LOS is further away than this get `None`.
+    pub fn set_search_window(&mut self, days: f64) {
+        self.search_window_days = days;
+    }
+
+    /// Sets the elevation the satellite has to rise above for AOS and sink
+    /// below for LOS, default is the geometric horizon at 0°.
+    ///
+    /// Negative values are treated as 0°.
+    pub fn set_aos_elevation(&mut self, deg: f64) {
+        self.aos_elevation_deg = deg.max(0.0);
+    }
+
+    /// Sets an azimuth dependent horizon given by (azimuth_deg, min_elevation_deg)
+    /// points, the elevation is interpolated linearly between them.
+    ///
+    /// AOS, LOS and `is_visible` use the higher of the mask and the AOS
+    /// elevation. Mask elevations below 0° are treated as 0°, an empty mask
+    /// removes it.
+    pub fn set_horizon_mask(&mut self, mask: Vec<(f64, f64)>) {
+        let mut mask: Vec<(f64, f64)> = mask.into_iter()
+                                            .map(|(az, el)| (az.rem_euclid(360.0), el.max(0.0)))
+                                            .collect();
+        mask.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        self.horizon_mask = mask;
+    }
+
+    /// Sets the atmospheric refraction added to the geometric elevation before
+    /// it is compared against the AOS elevation and horizon mask, default is
+    /// `RefractionModel::None`.
+    ///
+    /// Refraction moves AOS earlier and LOS later, by tens of seconds for
+    /// low passes. The elevation reported in `Sat` stays geometric, and passes
+    /// which only rise above the horizon due to refraction are not found.
+    pub fn set_refraction(&mut self, model: RefractionModel) {
+        self.refraction = model;
+    }
+
+    /// Sets how AOS and LOS are found, default is `Solver::Gpredict`.
+    ///
+    /// `Solver::Bisection` brackets each horizon crossing around the gpredict
+    /// result and bisects it to sub-second precision, e.g. for narrow beam
+    /// antennas, at the cost of a few dozen extra propagations per event.
+    /// AOS elevations, horizon masks and refraction are always solved this way.
+    pub fn set_pass_solver(&mut self, solver: Solver) {
+        self.solver = solver;
     }
 
-    pub fn update(&mut self, timeoption: Option<Epoch>) {
-        let juliantime = match timeoption {
-            Some(t) => t.as_jde_tai_days(),
-            None => unsafe {ffipredict::get_current_daynum()}
+    /// Sets a margin [deg] around the horizon for the `Tracker` events: AOS is
+    /// reported once the satellite is this far above the horizon and LOS once
+    /// it is this far below, so a grazing satellite does not flip back and
+    /// forth between them. Default is 0°, negative values are treated as 0°.
+    ///
+    /// Predicted AOS/LOS times are not affected.
+    pub fn set_event_hysteresis(&mut self, deg: f64) {
+        self.event_hysteresis_deg = deg.max(0.0);
+    }
+
+    /// Sets the earth radius [km] and flattening used for the geometry done
+    /// in this crate: the observer position, `Sat::coverage_circle`,
+    /// `Sat::ground_distance_km` and `Sat::faraday_rotation_rad`.
+    ///
+    /// Propagation, look angles and the sub-satellite point come from
+    /// SGP4/SDP4 which is defined in WGS-72 and are not affected. The
+    /// default is WGS-72.
+    pub fn set_earth_model(&mut self, radius_km: f64, flattening: f64) {
+        self.earth = EarthModel { radius_km: radius_km, flattening: flattening };
+        self.cache.clear();
+    }
+
+    #[deprecated(note = "use update_now or update_at instead")]
+    pub fn update(&mut self, timeoption: Option<DateTime<Utc>>) {
+        let _ = match timeoption {
+            Some(t) => self.update_at(t),
+            None => self.update_now(),
         };
+    }
+
+    /// Updates `self.sat` for the current time of the clock.
+    ///
+    /// On error `self.sat` is left unchanged.
+    pub fn update_now(&mut self) -> Result<(), PredictError> {
+        let now = self.now();
+        self.update_at(now)
+    }
+
+    /// Current time of the clock, the system time unless replaced by `set_clock`.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Replaces the time source used by `update_now`, e.g. with a
+    /// `FixedClock` in tests.
+    pub fn set_clock<C: Clock + Send + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Updates `self.sat` for the given time.
+    ///
+    /// On error `self.sat` is left unchanged.
+    pub fn update_at(&mut self, t: DateTime<Utc>) -> Result<(), PredictError> {
+        if self.tle_is_stale(t) {
+            return Err(PredictError::StaleTle(self.tle_age(t)));
+        }
+
+        self.update_jd(to_julian_date(t))
+    }
+
+    fn update_jd(&mut self, juliantime: f64) -> Result<(), PredictError> {
+        // predict_calc would silently give zeros
+        if !self.is_initialized() {
+            return Err(PredictError::NotInitialized);
+        }
+
+        // check the propagation first, the AOS/LOS search tells failures apart
+        // from missing events by the last propagated state
+        self.calc(juliantime);
+        if !self.is_propagation_valid() {
+            return Err(self.propagation_error());
+        }
 
         // we do not have AOS with some satellites, therefore option is used
-        let aos = match unsafe {ffipredict::find_aos(&mut self.p_sat, &mut self.p_qth, juliantime, 1.0)} {
-            n if n <= 0.0 => None,
-            aos => Some(Epoch::from_jde_tai(aos)),
+        let aos = self.next_aos(juliantime)?.map(from_julian_date);
+        let los = self.next_los(juliantime)?.map(from_julian_date);
+
+        self.calc(juliantime);
+
+        self.sat = Sat {
+            aos:                aos,
+            los:                los,
+            ..self.sat_state()
         };
-        let los = match unsafe {ffipredict::find_los(&mut self.p_sat, &mut self.p_qth, juliantime, 1.0)} {
-            n if n <= 0.0 => None,
-            los => Some(Epoch::from_jde_tai(los)),
+
+        Ok(())
+    }
+
+    /// Returns a snapshot of the satellite at the given time without touching `self.sat`.
+    ///
+    /// AOS and LOS are not searched for and are always `None`.
+    pub fn observe_at(&mut self, t: DateTime<Utc>) -> Sat {
+        let jd = to_julian_date(t);
+        if self.cache_capacity == 0 {
+            self.calc(jd);
+            return self.sat_state();
+        }
+
+        let key = (jd * 86400e3).round() as i64;
+        if let Some(i) = self.cache.iter().position(|&(k, _)| k == key) {
+            let entry = self.cache.remove(i).unwrap();
+            let sat = entry.1.clone();
+            self.cache.push_front(entry);
+            return sat;
+        }
+
+        self.calc(jd);
+        let sat = self.sat_state();
+        if self.cache.len() >= self.cache_capacity {
+            self.cache.pop_back();
+        }
+        self.cache.push_front((key, sat.clone()));
+
+        sat
+    }
+
+    /// Keeps the last `capacity` snapshots returned by `observe_at`, so
+    /// repeated queries for the same time, e.g. from several widgets of a UI,
+    /// are not propagated again.
+    ///
+    /// Times are compared rounded to milliseconds. The cache is emptied when
+    /// the location, elements or earth model change.
+    pub fn with_cache(mut self, capacity: usize) -> Predict {
+        self.cache_capacity = capacity;
+        self.cache.clear();
+        self
+    }
+
+    /// Empties the `observe_at` cache, see `with_cache`.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Snapshots for a series of `times`, e.g. the samples of a pass curve.
+    ///
+    /// Same as calling `observe_at` for each time, but the result is
+    /// allocated once and the `observe_at` cache is bypassed. libgpredict has
+    /// no batch entry point, so the propagator is still run per sample.
+    pub fn observe_many(&mut self, times: &[DateTime<Utc>]) -> Vec<Sat> {
+        let mut sats = Vec::with_capacity(times.len());
+        for t in times {
+            self.calc(to_julian_date(*t));
+            sats.push(self.sat_state());
+        }
+
+        sats
+    }
+
+    /// Like `observe_at` but for an observer at `observer`, e.g. a moving
+    /// vehicle fed with GPS fixes.
+    ///
+    /// The new location is kept for all following calls.
+    pub fn observe_from(&mut self, observer: Location, t: DateTime<Utc>) -> Sat {
+        self.set_location(observer);
+        self.observe_at(t)
+    }
+
+    /// Returns true if the satellite is above the horizon of this observer
+    /// and of `other` at time `t`, e.g. for store-and-forward relays.
+    ///
+    /// The AOS elevation and horizon mask only apply to this observer, the
+    /// geometric horizon is used for `other`.
+    pub fn mutual_visibility(&mut self, other: &Location, t: DateTime<Utc>) -> bool {
+        let jd = to_julian_date(t);
+        self.calc(jd);
+
+        let pos = [self.p_sat.pos.x, self.p_sat.pos.y, self.p_sat.pos.z];
+        let (_, other_el, _) = coords::look_angles(other.lat_deg, other.lon_deg, other.alt_m / 1000.0, &pos, jd);
+
+        self.clearance() > 0.0 && other_el > 0.0
+    }
+
+    /// Moves the observer to `location`.
+    pub fn set_location<T: Into<LLA>>(&mut self, location: T) {
+        let location_lla: LLA = location.into();
+
+        self.p_qth.lat = location_lla.lat_deg;
+        self.p_qth.lon = location_lla.lon_deg;
+        self.p_qth.alt = qth_altitude(location_lla.alt_m);
+        self.cache.clear();
+    }
+
+    /// Azimuth [deg] at the next AOS after `t`, `None` if there is none
+    /// within the search window.
+    pub fn next_aos_azimuth(&mut self, t: DateTime<Utc>) -> Option<f64> {
+        let aos = self.next_aos(to_julian_date(t)).ok()??;
+        Some(self.azimuth_at(aos))
+    }
+
+    /// Azimuth [deg] at the next LOS after `t`, `None` if there is none
+    /// within the search window.
+    pub fn next_los_azimuth(&mut self, t: DateTime<Utc>) -> Option<f64> {
+        let los = self.next_los(to_julian_date(t)).ok()??;
+        Some(self.azimuth_at(los))
+    }
+
+    /// Returns the next pass with AOS after the given time.
+    ///
+    /// `None` is returned if the satellite does not rise within the search
+    /// window or the propagation fails.
+    pub fn next_pass(&mut self, after: DateTime<Utc>) -> Option<Pass> {
+        let aos = self.next_aos(to_julian_date(after)).ok()??;
+        let los = self.next_los(aos).ok()??;
+        if los <= aos {
+            return None;
+        }
+
+        self.calc(aos);
+        let aos_azimuth_deg = self.p_sat.az;
+
+        self.calc(los);
+        let los_azimuth_deg = self.p_sat.az;
+
+        let (culmination, max_elevation_deg, _) = self.culmination(aos, los);
+        self.calc(culmination);
+        let ascending = coords::is_northbound(&[self.p_sat.pos.x, self.p_sat.pos.y, self.p_sat.pos.z],
+                                              &[self.p_sat.vel.x, self.p_sat.vel.y, self.p_sat.vel.z]);
+
+        Some(Pass {
+            aos:                from_julian_date(aos),
+            los:                from_julian_date(los),
+            culmination:        from_julian_date(culmination),
+            max_elevation_deg:  max_elevation_deg,
+            aos_azimuth_deg:    aos_azimuth_deg,
+            los_azimuth_deg:    los_azimuth_deg,
+            duration_secs:      ((los - aos) * 86400.0).round() as i64,
+            ascending:          ascending,
+        })
+    }
+
+    /// Returns up to `count` consecutive passes after `start` which reach at
+    /// least `min_elevation_deg`.
+    ///
+    /// The search stops after 14 days, so fewer passes are returned for
+    /// satellites which rarely or never rise.
+    pub fn upcoming_passes(&mut self, start: DateTime<Utc>, count: usize, min_elevation_deg: f64) -> Vec<Pass> {
+        let end = to_julian_date(start) + PASS_SEARCH_HORIZON_DAYS;
+
+        self.pass_iter(start, min_elevation_deg)
+            .take_while(|pass| to_julian_date(pass.aos) <= end)
+            .take(count)
+            .collect()
+    }
+
+    /// Returns an iterator over the passes after `start` which reach at least
+    /// `min_elevation_deg`, searching for each pass when it is requested.
+    ///
+    /// The iterator ends once no such pass was found for 14 days, so it
+    /// also ends for satellites which never rise.
+    pub fn pass_iter<'a>(&'a mut self, start: DateTime<Utc>, min_elevation_deg: f64) -> PassIter<'a> {
+        let t = to_julian_date(start);
+
+        PassIter {
+            predict:            self,
+            t:                  t,
+            give_up:            t + PASS_SEARCH_HORIZON_DAYS,
+            min_elevation_deg:  min_elevation_deg,
+        }
+    }
+
+    /// Returns the AOS, culmination and LOS events between `start` and `end`
+    /// in chronological order.
+    ///
+    /// Passes are searched for lazily while iterating. A pass in progress at
+    /// `start` only yields its remaining events, one still in progress at
+    /// `end` yields no LOS.
+    pub fn events_between<'a>(&'a mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> impl Iterator<Item = PassEvent> + 'a {
+        let start = to_julian_date(start);
+        let end = to_julian_date(end);
+        let mut t = start;
+        let mut pending = VecDeque::new();
+
+        self.calc(start);
+        if self.clearance() > 0.0 {
+            if let Ok(Some(los)) = self.next_los(start) {
+                if los > start {
+                    self.pass_events(None, start, los, end, &mut pending);
+                    t = los + 60.0 / 86400.0;
+                }
+            }
+        }
+
+        iter::from_fn(move || {
+            while pending.is_empty() && t < end {
+                let aos = match self.next_aos(t) {
+                    Ok(Some(aos)) => aos,
+                    Ok(None) => {
+                        // next_aos only looks one search window ahead
+                        t += self.search_window_days;
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                if aos > end {
+                    break;
+                }
+
+                let los = match self.next_los(aos) {
+                    Ok(Some(los)) if los > aos => los,
+                    Ok(_) => {
+                        t = aos + PASS_STEP_DAYS;
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+
+                self.pass_events(Some(aos), aos, los, end, &mut pending);
+                // continue the search shortly after LOS
+                t = los + 60.0 / 86400.0;
+            }
+
+            pending.pop_front()
+        })
+    }
+
+    /// Returns `(time, el_deg)` of the culmination of every pass in the `days`
+    /// after `start` which reaches at least `min_elevation_deg`.
+    pub fn peak_times(&mut self, start: DateTime<Utc>, days: u32, min_elevation_deg: f64) -> Vec<(DateTime<Utc>, f64)> {
+        let end = from_julian_date(to_julian_date(start) + days as f64);
+
+        self.events_between(start, end)
+            .filter_map(|event| match event {
+                PassEvent::Culmination { time, el_deg, .. } if el_deg >= min_elevation_deg => Some((time, el_deg)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// First time after `after` at which the sub-satellite longitude equals
+    /// `target_lon_deg`, e.g. for scheduling node crossings.
+    ///
+    /// Looks up to 14 days ahead, `None` if the longitude is not crossed
+    /// within that time, e.g. for geostationary satellites.
+    pub fn longitude_crossing(&mut self, target_lon_deg: f64, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // longitude east of the target, within [-180°, 180°)
+        let offset = |predict: &mut Predict, jd: f64| {
+            predict.calc(jd);
+            (predict.p_sat.ssplon - target_lon_deg + 540.0).rem_euclid(360.0) - 180.0
         };
 
+        let start = to_julian_date(after);
+        let end = start + PASS_SEARCH_HORIZON_DAYS;
+        let mut t0 = start;
+        let mut d0 = offset(self, t0);
+
+        while t0 < end {
+            let t1 = t0 + PASS_STEP_DAYS;
+            let d1 = offset(self, t1);
+
+            // a sign change by jumping across the antimeridian of the target is no crossing
+            if (d0 < 0.0) != (d1 < 0.0) && (d1 - d0).abs() < 180.0 {
+                let (mut a, mut b) = (t0, t1);
+                while b - a > CROSSING_PRECISION_DAYS {
+                    let t = (a + b) / 2.0;
+                    if (offset(self, t) < 0.0) == (d0 < 0.0) {
+                        a = t;
+                    }
+                    else {
+                        b = t;
+                    }
+                }
+                return Some(from_julian_date((a + b) / 2.0));
+            }
+
+            t0 = t1;
+            d0 = d1;
+        }
+
+        None
+    }
+
+    /// Returns (time, az_deg, el_deg) pointing samples `step` apart from AOS to LOS of `pass`.
+    ///
+    /// With `unwrap_azimuth` the azimuth is kept continuous instead of jumping
+    /// between 0° and 360°, so it may leave [0°, 360°) when the pass crosses
+    /// north. Rotators with overlap use this to avoid slewing the long way.
+    pub fn rotator_track(&mut self, pass: &Pass, step: Duration, unwrap_azimuth: bool) -> Vec<(DateTime<Utc>, f64, f64)> {
+        let aos = to_julian_date(pass.aos);
+        let los = to_julian_date(pass.los);
+        let step = step.as_secs_f64() / 86400.0;
+        let mut track: Vec<(DateTime<Utc>, f64, f64)> = Vec::new();
+        let mut t = aos;
+
+        loop {
+            self.calc(t);
+            let mut az = self.p_sat.az;
+
+            if unwrap_azimuth {
+                if let Some(&(_, prev_az, _)) = track.last() {
+                    az += 360.0 * ((prev_az - az) / 360.0).round();
+                }
+            }
+
+            track.push((from_julian_date(t), az, self.p_sat.el));
+
+            if t >= los || step <= 0.0 {
+                break;
+            }
+            t = (t + step).min(los);
+        }
+
+        track
+    }
+
+    /// Returns the time and slant range [km] of the closest approach during `pass`.
+    ///
+    /// This is not necessarily the culmination, e.g. for observers at an
+    /// altitude. The minimum may also be at AOS or LOS.
+    pub fn closest_approach(&mut self, pass: &Pass) -> (DateTime<Utc>, f64) {
+        let aos = to_julian_date(pass.aos);
+        let los = to_julian_date(pass.los);
+
+        // coarse sampling to bracket the minimum
+        let mut best = (aos, self.range_at(aos));
+        let mut t = aos;
+        while t < los {
+            t = (t + PASS_STEP_DAYS).min(los);
+            let range = self.range_at(t);
+            if range < best.1 {
+                best = (t, range);
+            }
+        }
+
+        // golden-section search within one step of the best sample
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let mut a = (best.0 - PASS_STEP_DAYS).max(aos);
+        let mut b = (best.0 + PASS_STEP_DAYS).min(los);
+        while b - a > CROSSING_PRECISION_DAYS {
+            let c = b - ratio * (b - a);
+            let d = a + ratio * (b - a);
+            if self.range_at(c) < self.range_at(d) {
+                b = d;
+            }
+            else {
+                a = c;
+            }
+        }
+
+        let t = (a + b) / 2.0;
+        let range = self.range_at(t);
+        if range < best.1 {
+            best = (t, range);
+        }
+
+        (from_julian_date(best.0), best.1)
+    }
+
+    /// Sun position in ECI coordinates [km] at time `t`.
+    pub fn sun_position(&self, t: DateTime<Utc>) -> [f64; 3] {
+        sun::position(t)
+    }
+
+    /// Returns true if the satellite can be seen optically at `t`: it is above
+    /// the horizon (including refraction, the AOS elevation and horizon mask)
+    /// and sunlit while the observer is in darkness.
+    ///
+    /// The satellite is propagated to `t` like `observe_at`, `self.sat` is not
+    /// touched.
+    pub fn is_visible(&mut self, t: DateTime<Utc>) -> bool {
+        let sat = self.observe_at(t);
+        let jd = to_julian_date(t);
+        let sun = sun::position_jd(jd);
+        let (_, sun_el, _) = coords::look_angles(self.p_qth.lat, self.p_qth.lon, self.p_qth.alt as f64 / 1000.0, &sun, jd);
+
+        self.horizon_clearance(sat.el_deg, sat.az_deg) > 0.0 && sun_el < TWILIGHT_ELEVATION_DEG
+            && sat.is_sunlit(t)
+    }
+
+    /// Fraction of the orbit starting at `t` which is spent in the earth's
+    /// shadow, e.g. for sizing batteries.
+    ///
+    /// One orbital period is sampled every 10 seconds, `self.sat` is not
+    /// touched.
+    pub fn eclipse_fraction(&mut self, t: DateTime<Utc>) -> f64 {
+        let start = to_julian_date(t);
+        let period = self.orbital_period_secs() / 86400.0;
+        let samples = (period / PASS_STEP_DAYS).ceil().max(1.0) as usize;
+
+        let eclipsed = (0..samples).filter(|&i| {
+            let jd = start + period * i as f64 / samples as f64;
+            self.calc(jd);
+            let pos = [self.p_sat.pos.x, self.p_sat.pos.y, self.p_sat.pos.z];
+            sun::is_eclipsed(&pos, &sun::position_jd(jd))
+        }).count();
+
+        eclipsed as f64 / samples as f64
+    }
+
+    /// Returns the sub-satellite point `(time, lat_deg, lon_deg)` at `count`
+    /// times `step` apart, starting at `start`.
+    ///
+    /// Longitudes are within [-180°, 180°], a jump of more than 180° between
+    /// two consecutive points means the track crossed the antimeridian.
+    pub fn ground_track<'a>(&'a mut self, start: DateTime<Utc>, step: Duration, count: usize)
+        -> impl Iterator<Item=(DateTime<Utc>, f64, f64)> + 'a {
+        let start = to_julian_date(start);
+        let step = step.as_secs_f64() / 86400.0;
+
+        (0..count).map(move |i| {
+            let t = start + i as f64 * step;
+            self.calc(t);
+            (from_julian_date(t), self.p_sat.ssplat, self.p_sat.ssplon)
+        })
+    }
+
+    /// Returns the `ground_track` as a GeoJSON geometry, a `LineString` or a
+    /// `MultiLineString` split where the track crosses the antimeridian.
+    #[cfg(feature = "serde")]
+    pub fn ground_track_geojson(&mut self, start: DateTime<Utc>, step: Duration, count: usize) -> String {
+        let points: Vec<(f64, f64)> = self.ground_track(start, step, count)
+                                          .map(|(_, lat, lon)| (lat, lon))
+                                          .collect();
+        let mut lines = split_antimeridian(&points);
+
+        let geometry = if lines.len() == 1 {
+            GeoJsonGeometry::LineString { coordinates: lines.remove(0) }
+        }
+        else {
+            GeoJsonGeometry::MultiLineString { coordinates: lines }
+        };
+
+        // only f64 coordinates, serialization can't fail
+        serde_json::to_string(&geometry).expect("ground track json")
+    }
+
+    // next time the satellite rises above the AOS elevation, `None` if there
+    // is none within the search window
+    fn next_aos(&mut self, start: f64) -> Result<Option<f64>, PredictError> {
+        if self.is_geometric_horizon() {
+            return self.find_aos(start);
+        }
+
+        let end = start + self.search_window_days;
+        let mut t = start;
+
+        self.calc(t);
+        if self.p_sat.el > 0.0 {
+            // already above the horizon, the AOS elevation may still be reached in this pass
+            let los = match self.find_los(t)? {
+                Some(los) => los,
+                None => return Ok(None),
+            };
+            if let Some(aos) = self.find_crossing(t, los + self.crossing_margin(), true) {
+                return Ok(Some(aos));
+            }
+            t = los + 60.0 / 86400.0;
+        }
+
+        while t < end {
+            let aos = match self.find_aos(t)? {
+                Some(aos) if aos <= end => aos,
+                _ => return Ok(None),
+            };
+            let los = match self.find_los(aos)? {
+                Some(los) if los > aos => los,
+                _ => return Ok(None),
+            };
+            let margin = self.crossing_margin();
+            if let Some(aos) = self.find_crossing((aos - margin).max(t), los + margin, true) {
+                return Ok(Some(aos));
+            }
+            t = los + 60.0 / 86400.0;
+        }
+
+        Ok(None)
+    }
+
+    // next time the satellite sinks below the AOS elevation, `None` if there
+    // is none within the search window
+    fn next_los(&mut self, start: f64) -> Result<Option<f64>, PredictError> {
+        if self.is_geometric_horizon() {
+            return self.find_los(start);
+        }
+
+        self.calc(start);
+        let from = if self.clearance() >= 0.0 {
+            start
+        }
+        else {
+            match self.next_aos(start)? {
+                Some(aos) => aos,
+                None => return Ok(None),
+            }
+        };
+
+        // the gpredict search stops right away when started just above the
+        // horizon, e.g. at a bisected AOS, so it starts a margin earlier
+        let margin = self.crossing_margin();
+        match self.find_los(from - margin)? {
+            Some(los) if los > from => Ok(Some(self.find_crossing(from, los + margin, false).unwrap_or(los))),
+            // still up after the LOS found by gpredict, e.g. due to refraction
+            _ if margin > 0.0 => Ok(self.find_crossing(from, from + margin, false)),
+            _ => Ok(None),
+        }
+    }
+
+    // queues the events of the pass visible from `from` until `los`, without
+    // AOS if the pass was already in progress at `from`
+    fn pass_events(&mut self, aos: Option<f64>, from: f64, los: f64, end: f64, events: &mut VecDeque<PassEvent>) {
+        if let Some(aos) = aos {
+            self.calc(aos);
+            events.push_back(PassEvent::Aos {
+                time:       from_julian_date(aos),
+                az_deg:     self.p_sat.az,
+            });
+        }
+
+        let (t, el_deg, az_deg) = self.culmination(from, los);
+        if (aos.is_some() || t > from) && t <= end {
+            events.push_back(PassEvent::Culmination {
+                time:       from_julian_date(t),
+                el_deg:     el_deg,
+                az_deg:     az_deg,
+            });
+        }
+
+        if los <= end {
+            self.calc(los);
+            events.push_back(PassEvent::Los {
+                time:       from_julian_date(los),
+                az_deg:     self.p_sat.az,
+            });
+        }
+    }
+
+    fn azimuth_at(&mut self, jd: f64) -> f64 {
+        self.calc(jd);
+        self.p_sat.az
+    }
+
+    fn range_at(&mut self, jd: f64) -> f64 {
+        self.calc(jd);
+        self.p_sat.range
+    }
+
+    // returns (time, el, az) of the highest point between `from` and `to`,
+    // sampled every PASS_STEP_DAYS and refined by bisecting the sign of the
+    // elevation rate
+    fn culmination(&mut self, from: f64, to: f64) -> (f64, f64, f64) {
+        let mut best = (to, self.elevation_at(to));
+        let mut t = from;
+        while t < to {
+            let el = self.elevation_at(t);
+            if el > best.1 {
+                best = (t, el);
+            }
+            t += PASS_STEP_DAYS;
+        }
+
+        let mut a = (best.0 - PASS_STEP_DAYS).max(from);
+        let mut b = (best.0 + PASS_STEP_DAYS).min(to);
+        let t = if b - a <= CROSSING_PRECISION_DAYS {
+            // grazing pass
+            (from + to) / 2.0
+        }
+        else if self.elevation_rate(a) > 0.0 && self.elevation_rate(b) < 0.0 {
+            while b - a > CROSSING_PRECISION_DAYS {
+                let mid = (a + b) / 2.0;
+                if self.elevation_rate(mid) > 0.0 {
+                    a = mid;
+                }
+                else {
+                    b = mid;
+                }
+            }
+            (a + b) / 2.0
+        }
+        else {
+            // highest at one end of the interval
+            best.0
+        };
+
+        self.calc(t);
+        (t, self.p_sat.el, self.p_sat.az)
+    }
+
+    fn elevation_at(&mut self, jd: f64) -> f64 {
+        self.calc(jd);
+        self.p_sat.el
+    }
+
+    // elevation difference across CROSSING_PRECISION_DAYS around `jd`
+    fn elevation_rate(&mut self, jd: f64) -> f64 {
+        let h = CROSSING_PRECISION_DAYS / 2.0;
+        self.elevation_at(jd + h) - self.elevation_at(jd - h)
+    }
+
+    // first time within [start, end] at which the satellite rises above
+    // (or with `rising` false sinks below) the AOS elevation
+    fn find_crossing(&mut self, start: f64, end: f64, rising: bool) -> Option<f64> {
+        let mut t0 = start;
+        self.calc(t0);
+        let mut above = self.clearance() >= 0.0;
+
+        while t0 < end {
+            let t1 = (t0 + PASS_STEP_DAYS).min(end);
+            self.calc(t1);
+            let now_above = self.clearance() >= 0.0;
+
+            if now_above != above && now_above == rising {
+                return Some(self.bisect_crossing(t0, t1));
+            }
+
+            above = now_above;
+            t0 = t1;
+        }
+
+        None
+    }
+
+    // bisects a crossing of the AOS elevation between t0 and t1, returns the
+    // first time on the far side of the crossing
+    fn bisect_crossing(&mut self, mut t0: f64, mut t1: f64) -> f64 {
+        self.calc(t0);
+        let above_at_t0 = self.clearance() >= 0.0;
+
+        while t1 - t0 > CROSSING_PRECISION_DAYS {
+            let t = (t0 + t1) / 2.0;
+            self.calc(t);
+            if (self.clearance() >= 0.0) == above_at_t0 {
+                t0 = t;
+            }
+            else {
+                t1 = t;
+            }
+        }
+
+        t1
+    }
+
+    // whether `self.sat` is up for the tracker: a satellite which was up
+    // (`was_up`) stays up until it sinks the event hysteresis below the
+    // horizon, one which was down has to rise as far above it
+    pub(crate) fn is_above_horizon(&self, was_up: bool) -> bool {
+        let margin = if was_up { -self.event_hysteresis_deg } else { self.event_hysteresis_deg };
+        self.horizon_clearance(self.sat.el_deg, self.sat.az_deg) > margin
+    }
+
+    // gtk_sat_data_init_sat has run the propagator once, which sets the SGP4 or
+    // SDP4 initialized flag
+    fn is_initialized(&self) -> bool {
+        let flags = self.p_sat.flags;
+
+        // the native SGP4 has no SDP4, deep space satellites are initialized
+        // but fail with DeepSpaceUnsupported on propagation
+        if cfg!(feature = "pure-rust") && flags & ffipredict::DEEP_SPACE_EPHEM_FLAG != 0 {
+            return true;
+        }
+
+        flags & (ffipredict::SGP4_INITIALIZED_FLAG | ffipredict::SDP4_INITIALIZED_FLAG) != 0
+    }
+
+    // the last calc gave usable values
+    fn is_propagation_valid(&self) -> bool {
+        self.p_sat.range.is_finite() && self.p_sat.el.is_finite() && self.p_sat.alt.is_finite()
+            && self.p_sat.alt > 0.0
+    }
+
+    fn clearance(&self) -> f64 {
+        self.horizon_clearance(self.p_sat.el, self.p_sat.az)
+    }
+
+    // apparent elevation, including refraction, above the AOS elevation and
+    // horizon mask [deg], the one horizon check behind all visibility tests
+    fn horizon_clearance(&self, el_deg: f64, az_deg: f64) -> f64 {
+        el_deg + self.refraction_deg(el_deg) - self.horizon_elevation(az_deg)
+    }
+
+    fn refraction_deg(&self, el_deg: f64) -> f64 {
+        self.refraction.correction_deg(el_deg, self.p_qth.alt as f64 / 1000.0)
+    }
+
+    // extra search time around the AOS/LOS found by gpredict for crossings
+    // which can lie outside of it [days]
+    fn crossing_margin(&self) -> f64 {
+        if self.refraction != RefractionModel::None {
+            REFRACTION_SEARCH_DAYS
+        }
+        else if self.solver == Solver::Bisection {
+            SOLVER_SEARCH_DAYS
+        }
+        else {
+            0.0
+        }
+    }
+
+    // minimum elevation for AOS at the given azimuth [deg]
+    fn horizon_elevation(&self, az_deg: f64) -> f64 {
+        mask_elevation(&self.horizon_mask, az_deg).max(self.aos_elevation_deg)
+    }
+
+    // AOS/LOS are plain horizon crossings which gpredict finds on its own
+    fn is_geometric_horizon(&self) -> bool {
+        self.solver == Solver::Gpredict && self.refraction == RefractionModel::None && self.aos_elevation_deg == 0.0 && self.horizon_mask.iter().all(|&(_, el)| el == 0.0)
+    }
+
+    fn find_aos(&mut self, juliantime: f64) -> Result<Option<f64>, PredictError> {
+        let aos = unsafe {ffipredict::find_aos(&mut self.p_sat, &mut self.p_qth, juliantime, self.search_window_days)};
+        self.search_result(aos)
+    }
+
+    fn find_los(&mut self, juliantime: f64) -> Result<Option<f64>, PredictError> {
+        let los = unsafe {ffipredict::find_los(&mut self.p_sat, &mut self.p_qth, juliantime, self.search_window_days)};
+        self.search_result(los)
+    }
+
+    // libgpredict returns 0.0 both when there is no AOS/LOS within the search
+    // window and when the propagation failed during the search, the last
+    // propagated state tells them apart
+    fn search_result(&self, jd: f64) -> Result<Option<f64>, PredictError> {
+        if jd > 0.0 {
+            Ok(Some(jd))
+        }
+        else if self.is_propagation_valid() {
+            Ok(None)
+        }
+        else {
+            Err(self.propagation_error())
+        }
+    }
+
+    // why the last calc gave no usable values
+    fn propagation_error(&self) -> PredictError {
+        if cfg!(feature = "pure-rust") && self.is_deep_space() {
+            PredictError::DeepSpaceUnsupported
+        }
+        else {
+            PredictError::PropagationDiverged
+        }
+    }
+
+    fn calc(&mut self, juliantime: f64) {
         unsafe {ffipredict::predict_calc(&mut self.p_sat, &mut self.p_qth, juliantime)};
+    }
 
-        self.sat.aos                = aos;
-        self.sat.los                = los;
-        self.sat.az_deg             = self.p_sat.az;
-        self.sat.el_deg             = self.p_sat.el;
-        self.sat.range_km           = self.p_sat.range;
-        self.sat.range_rate_km_sec  = self.p_sat.range_rate;
-        self.sat.lat_deg            = self.p_sat.ssplat;
-        self.sat.lon_deg            = self.p_sat.ssplon;
-        self.sat.alt_km             = self.p_sat.alt;
-        self.sat.vel_km_s           = self.p_sat.velo;
-        self.sat.orbit_nr           = self.p_sat.orbit as u64;
+    // satellite data from the last predict_calc run
+    fn sat_state(&self) -> Sat {
+        let pos = [self.p_sat.pos.x, self.p_sat.pos.y, self.p_sat.pos.z];
+        let alt_km = self.p_qth.alt as f64 / 1000.0;
+        let (ra, dec) = coords::ra_dec(self.p_qth.lat, self.p_qth.lon, alt_km, &pos, self.p_sat.jul_utc);
+        let observer = self.earth.geodetic_to_eci(self.p_qth.lat, self.p_qth.lon, alt_km, self.p_sat.jul_utc);
+        let topocentric = coords::eci_to_topocentric(self.p_qth.lat, self.p_qth.lon, alt_km, &pos, self.p_sat.jul_utc);
+        let vel = [self.p_sat.vel.x, self.p_sat.vel.y, self.p_sat.vel.z];
+        let (az_rate, el_rate) = coords::look_angle_rates(self.p_qth.lat, self.p_qth.lon, alt_km,
+                                                          &pos, &vel, self.p_sat.jul_utc);
+
+        Sat {
+            aos:                None,
+            los:                None,
+            az_deg:             self.p_sat.az,
+            el_deg:             self.p_sat.el,
+            az_rate_deg_s:      az_rate,
+            el_rate_deg_s:      el_rate,
+            range_km:           self.p_sat.range,
+            range_rate_km_sec:  self.p_sat.range_rate,
+            lat_deg:            self.p_sat.ssplat,
+            lon_deg:            self.p_sat.ssplon,
+            alt_km:             self.p_sat.alt,
+            vel_km_s:           self.p_sat.velo,
+            orbit_nr:           self.p_sat.orbit as u64,
+            footprint_km:       self.p_sat.footprint,
+            // gpredict keeps the mean anomaly scaled to 0-256
+            mean_anomaly_deg:   self.p_sat.ma * 360.0 / 256.0,
+            phase_deg:          self.p_sat.phase,
+            right_ascension_deg: ra,
+            declination_deg:    dec,
+            eci_pos_km:         pos,
+            eci_vel_km_s:       vel,
+            observer_eci_km:    observer,
+            topocentric_km:     topocentric,
+            earth:              self.earth,
+        }
     }
 }
 
+// whether the orbit inclination and apogee allow the satellite to rise above
+// the horizon at the observer's latitude, same as gpredict's has_aos
+pub(crate) fn can_rise(sat: &ffipredict::sat_t, qth: &ffipredict::qth_t) -> bool {
+    if sat.meanmo == 0.0 {
+        return false;
+    }
+
+    // xincl is already in rad by select_ephemeris
+    let mut lin = sat.tle.xincl;
+    if lin >= PI / 2.0 {
+        lin = PI - lin;
+    }
+
+    let sma = 331.25 * ((1440.0 / sat.meanmo).ln() * (2.0 / 3.0)).exp();
+    let apogee = sma * (1.0 + sat.tle.eo) - coords::EARTH_RADIUS_KM;
+
+    (coords::EARTH_RADIUS_KM / (apogee + coords::EARTH_RADIUS_KM)).acos() + lin > qth.lat.to_radians().abs()
+}
+
+/// GeoJSON geometries written by `Predict::ground_track_geojson`
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum GeoJsonGeometry {
+    LineString { coordinates: Vec<[f64; 2]> },
+    MultiLineString { coordinates: Vec<Vec<[f64; 2]>> },
+}
+
+// splits (lat_deg, lon_deg) points into [lon, lat] lines which end and start
+// at the antimeridian where consecutive points are more than 180° apart
+#[cfg(any(feature = "serde", test))]
+fn split_antimeridian(points: &[(f64, f64)]) -> Vec<Vec<[f64; 2]>> {
+    let wrap = |lon: f64| (lon + 180.0).rem_euclid(360.0) - 180.0;
+    let mut lines = vec![Vec::new()];
+
+    for (i, &(lat, lon)) in points.iter().enumerate() {
+        let lon = wrap(lon);
+
+        if i > 0 {
+            let (prev_lat, prev_lon) = (points[i - 1].0, wrap(points[i - 1].1));
+            if (lon - prev_lon).abs() > 180.0 {
+                // latitude where the shorter way around meets ±180°
+                let edge = if prev_lon > 0.0 { 180.0 } else { -180.0 };
+                let dlon = lon + 2.0 * edge - prev_lon;
+                let cross_lat = prev_lat + (lat - prev_lat) * (edge - prev_lon) / dlon;
+
+                lines.last_mut().unwrap().push([edge, cross_lat]);
+                lines.push(vec![[-edge, cross_lat]]);
+            }
+        }
+
+        lines.last_mut().unwrap().push([lon, lat]);
+    }
+
+    lines
+}
+
+// libgpredict keeps the observer altitude in whole meters, rounded instead of
+// truncated towards zero so stations below sea level are not moved up
+fn qth_altitude(alt_m: f64) -> i32 {
+    alt_m.round() as i32
+}
+
+// linear interpolation of a horizon mask sorted by azimuth, wrapping at 360°
+fn mask_elevation(mask: &[(f64, f64)], az_deg: f64) -> f64 {
+    let az = az_deg.rem_euclid(360.0);
+    let interpolate = |(a0, e0): (f64, f64), (a1, e1): (f64, f64)| {
+        if a1 - a0 > 0.0 { e0 + (e1 - e0) * (az - a0) / (a1 - a0) } else { e1 }
+    };
+
+    let last = match mask.last() {
+        Some(&(a, e)) => (a - 360.0, e),
+        None => return 0.0,
+    };
+
+    let mut prev = last;
+    for &point in mask {
+        if az <= point.0 {
+            return interpolate(prev, point);
+        }
+        prev = point;
+    }
+
+    interpolate(prev, (mask[0].0 + 360.0, mask[0].1))
+}
+
 #[test]
 fn predict_location_formats() {
     use coordinates::ECEF;
+    let (tle, _) = ::grifex();
+    let lla = LLA { lat_deg: 0.,
+                    lon_deg: 0.,
+                    alt_m:   0., };
+    let ecef = ECEF { x: 0.,
+                      y: 0.,
+                      z: 0., };
+
+    Predict::new(&tle, &lla).unwrap();
+    Predict::new(&tle, lla).unwrap();
+    Predict::new(&tle, &ecef).unwrap();
+    Predict::new(&tle, ecef).unwrap();
+}
+
+#[test]
+fn predict_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Predict>();
+}
+
+#[test]
+fn predict_periods() {
+    let (tle, _) = ::grifex();
+    let predict = Predict::new(&tle, LLA { lat_deg: 0., lon_deg: 0., alt_m: 0. }).unwrap();
+
+    assert!((predict.orbital_period_secs() - 5730.8).abs() < 0.1);
+    assert!((predict.nodal_period_secs() - predict.orbital_period_secs()).abs() < 30.);
+    assert_eq!(predict.theoretical_max_elevation(), 90.);
+}
+
+#[test]
+fn predict_revolution_at() {
+    let (tle, _) = ::grifex();
+    let mut predict = Predict::new(&tle, LLA { lat_deg: 0., lon_deg: 0., alt_m: 0. }).unwrap();
+    let epoch = to_julian_date(tle.epoch().unwrap());
+
+    // mean anomaly at epoch is 351.4°, perigee follows within 0.03 revolutions
+    assert_eq!(predict.revolution_at(from_julian_date(epoch)), 3187);
+    assert_eq!(predict.revolution_at(from_julian_date(epoch + 0.01)), 3188);
+    assert_eq!(predict.revolution_at(from_julian_date(epoch + 1.)), 3203);
+}
+
+#[test]
+fn predict_bad_tle() {
     let tle = tle::Tle {
         name: "GRIFEX".to_string(),
         line1: "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993".to_string(),
-        line2: "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889".to_string()
+        line2: "2 40379  99.1124 290.6779 0157088 \0 8.9691 351.4280 15.07659299 31889".to_string()
     };
     let lla = LLA { lat_deg: 0.,
                     lon_deg: 0.,
                     alt_m:   0., };
-    let ecef = ECEF { x: 0.,
-                      y: 0.,
-                      z: 0., };
 
-    Predict::new(&tle, &lla);
-    Predict::new(&tle, lla);
-    Predict::new(&tle, &ecef);
-    Predict::new(&tle, ecef);
+    assert_eq!(Predict::new(&tle, lla).unwrap_err(), PredictError::TleParse("line2"));
+
+    let short = tle::Tle { line2: tle.line2[..60].to_string(), ..tle };
+    assert_eq!(Predict::new(&short, lla).unwrap_err(),
+               PredictError::InvalidTle(TleError::BadLineLength { line: 2, got: 60 }));
+}
+
+#[test]
+fn predict_not_initialized() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let t = from_julian_date(2457266.5);
+    assert!(predict.update_at(t).is_ok());
+
+    predict.p_sat.flags = 0;
+    assert_eq!(predict.update_at(t), Err(PredictError::NotInitialized));
+}
+
+#[test]
+fn predict_eclipse_fraction() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+
+    // a low earth orbit spends roughly a third of the time in the shadow
+    let fraction = predict.eclipse_fraction(from_julian_date(2457266.5));
+    assert!(fraction > 0.2 && fraction < 0.45, "{}", fraction);
+}
+
+#[test]
+fn predict_longitude_crossing() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let start = from_julian_date(2457266.5);
+
+    for &lon in [0., 23.15163, -170., 179.9].iter() {
+        let t = predict.longitude_crossing(lon, start).unwrap();
+        assert!(to_julian_date(t) > to_julian_date(start));
+
+        let sat = predict.observe_at(t);
+        assert!(((sat.lon_deg - lon + 540.) % 360. - 180.).abs() < 0.01, "{} {}", lon, sat.lon_deg);
+    }
+}
+
+#[test]
+fn predict_never_rises() {
+    // equatorial orbit, below the horizon at 58° north
+    let (grifex, location) = ::grifex();
+    let tle = tle::Tle {
+        line2: "2 40379   5.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31886".to_string(),
+        ..grifex
+    };
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let t = from_julian_date(2457266.5);
+
+    assert_eq!(predict.update_at(t), Ok(()));
+    assert!(predict.sat.aos.is_none() && predict.sat.los.is_none());
+    assert!(predict.next_pass(t).is_none());
+    assert!(predict.next_aos_azimuth(t).is_none());
+    assert!(predict.theoretical_max_elevation() < 0.);
+
+    predict.set_location(Location { lat_deg: 10., lon_deg: 23.15163, alt_m: 8. });
+    let max_elevation = predict.theoretical_max_elevation();
+    assert!(max_elevation > 40. && max_elevation < 50.);
+}
+
+#[test]
+fn predict_observe_cache() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap().with_cache(2);
+    let t = from_julian_date(2457266.5);
+
+    let first = predict.observe_at(t);
+    predict.observe_at(from_julian_date(2457266.6));
+    let cached = predict.observe_at(t);
+    assert_eq!((first.az_deg, first.el_deg, first.range_km), (cached.az_deg, cached.el_deg, cached.range_km));
+    assert_eq!(predict.cache.len(), 2);
+
+    predict.observe_at(from_julian_date(2457266.7));
+    assert_eq!(predict.cache.len(), 2);
+
+    // a new location invalidates the snapshots
+    predict.set_location(Location { lat_deg: -33.9, lon_deg: -70.7, alt_m: 500. });
+    assert!(predict.cache.is_empty());
+    assert!(predict.observe_at(t).az_deg != first.az_deg);
+
+    predict.clear_cache();
+    assert!(predict.cache.is_empty());
+}
+
+#[test]
+fn predict_reset_sat() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    predict.update_at(from_julian_date(2457266.5)).unwrap();
+    assert!(predict.sat.range_km > 0. && predict.sat.aos.is_some());
+
+    predict.reset_sat();
+    assert_eq!(predict.sat.range_km, 0.);
+    assert!(predict.sat.aos.is_none() && predict.sat.los.is_none());
+}
+
+#[test]
+fn predict_horizon_mask() {
+    let mask = vec![(0., 10.), (90., 0.), (180., 20.)];
+
+    assert_eq!(mask_elevation(&[], 123.), 0.);
+    assert_eq!(mask_elevation(&mask, 0.), 10.);
+    assert_eq!(mask_elevation(&mask, 45.), 5.);
+    assert_eq!(mask_elevation(&mask, 135.), 10.);
+    assert_eq!(mask_elevation(&mask, 270.), 15.);
+    assert_eq!(mask_elevation(&mask, 360. + 45.), 5.);
+}
+
+#[test]
+fn predict_events_between() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let start = from_julian_date(2457266.5);
+    let end = from_julian_date(2457267.5);
+
+    let events: Vec<PassEvent> = predict.events_between(start, end).collect();
+    assert!(events.len() >= 3);
+
+    let mut up = false;
+    let mut last = to_julian_date(start);
+    for event in events.iter() {
+        let t = to_julian_date(event.time());
+        assert!(t >= last && t <= to_julian_date(end));
+        last = t;
+
+        match *event {
+            PassEvent::Aos { .. } => { assert!(!up); up = true; }
+            PassEvent::Culmination { el_deg, .. } => { assert!(up && el_deg > 0.); }
+            PassEvent::Los { .. } => { assert!(up); up = false; }
+        }
+    }
+}
+
+#[test]
+fn predict_reference_ephemeris() {
+    let tle = ::spacetrack_report_3();
+    let mut predict = Predict::new(&tle, Location::default()).unwrap();
+    let epoch = to_julian_date(tle.epoch().unwrap());
+
+    for &(tsince, pos, vel) in ::SPACETRACK_REPORT_3_EPHEMERIS.iter() {
+        let sat = predict.observe_at(from_julian_date(epoch + tsince / 1440.0));
+        let (eci_pos, eci_vel) = (sat.eci_position_km(), sat.eci_velocity_km_s());
+        for i in 0..3 {
+            assert!((eci_pos[i] - pos[i]).abs() < 0.01, "position at {}: {:?} != {:?}", tsince, eci_pos, pos);
+            assert!((eci_vel[i] - vel[i]).abs() < 1e-5, "velocity at {}: {:?} != {:?}", tsince, eci_vel, vel);
+        }
+    }
+}
+
+#[test]
+fn predict_refraction() {
+    assert_eq!(RefractionModel::None.correction_deg(0., 0.), 0.);
+    assert!((RefractionModel::Radio.correction_deg(0., 0.) - 0.761).abs() < 1e-3);
+    assert!((RefractionModel::Optical.correction_deg(0., 0.) - 0.483).abs() < 1e-3);
+    assert!(RefractionModel::Radio.correction_deg(0., 1.) < RefractionModel::Radio.correction_deg(0., 0.));
+    assert!(RefractionModel::Optical.correction_deg(90., 0.) < 1e-3);
+
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let start = from_julian_date(2457266.5);
+
+    let geometric = predict.next_pass(start).unwrap();
+    predict.set_refraction(RefractionModel::Optical);
+    let optical = predict.next_pass(start).unwrap();
+    predict.set_refraction(RefractionModel::Radio);
+    let radio = predict.next_pass(start).unwrap();
+
+    let secs = |a: DateTime<Utc>, b: DateTime<Utc>| (to_julian_date(a) - to_julian_date(b)) * 86400.0;
+    assert!(secs(geometric.aos, optical.aos) > 1. && secs(optical.aos, radio.aos) > 1.);
+    assert!(secs(optical.los, geometric.los) > 1. && secs(radio.los, optical.los) > 1.);
+    assert!(secs(geometric.aos, radio.aos) < 120.);
+}
+
+#[test]
+fn predict_split_antimeridian() {
+    let lines = split_antimeridian(&[(0., 170.), (10., 178.), (20., -178.), (30., -170.)]);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], vec![[170., 0.], [178., 10.], [180., 15.]]);
+    assert_eq!(lines[1], vec![[-180., 15.], [-178., 20.], [-170., 30.]]);
+
+    let lines = split_antimeridian(&[(0., -179.), (1., 179.), (2., 360. - 178.)]);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], vec![[-179., 0.], [-180., 0.5]]);
+    assert_eq!(lines[2][1], [-178., 2.]);
+
+    assert_eq!(split_antimeridian(&[(0., 10.), (1., 20.)]).len(), 1);
+}
+
+#[test]
+fn predict_observe_many() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let times: Vec<DateTime<Utc>> = (0..5).map(|i| from_julian_date(2457266.5 + i as f64 / 1440.0)).collect();
+
+    let sats = predict.observe_many(&times);
+    assert_eq!(sats.len(), times.len());
+    for (sat, &t) in sats.iter().zip(times.iter()) {
+        let single = predict.observe_at(t);
+        assert_eq!((sat.az_deg, sat.el_deg, sat.range_km), (single.az_deg, single.el_deg, single.range_km));
+    }
+    assert!(predict.observe_many(&[]).is_empty());
+}
+
+#[test]
+fn predict_below_sea_level() {
+    let (tle, _) = ::grifex();
+    let t = from_julian_date(2457266.5);
+
+    let mut predict = Predict::new(&tle, Location { lat_deg: 31.5, lon_deg: 35.5, alt_m: 0. }).unwrap();
+    let sea_level = predict.observe_at(t).range_km;
+
+    predict.set_location(Location { lat_deg: 31.5, lon_deg: 35.5, alt_m: -400.7 });
+    assert_eq!(predict.p_qth.alt, -401);
+    let dead_sea = predict.observe_at(t).range_km;
+    assert!(dead_sea != sea_level && (dead_sea - sea_level).abs() < 0.401);
+}
+
+#[test]
+fn predict_bisection_solver() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    let start = from_julian_date(2457266.5);
+
+    let coarse = predict.next_pass(start).unwrap();
+    predict.set_pass_solver(Solver::Bisection);
+    let fine = predict.next_pass(start).unwrap();
+
+    let secs = |a: DateTime<Utc>, b: DateTime<Utc>| (to_julian_date(a) - to_julian_date(b)).abs() * 86400.0;
+    assert!(secs(coarse.aos, fine.aos) < 5. && secs(coarse.los, fine.los) < 5.);
+
+    // the crossings are bracketed to CROSSING_PRECISION_DAYS
+    let el = |predict: &mut Predict, jd: f64| predict.observe_at(from_julian_date(jd)).el_deg;
+    let aos = to_julian_date(fine.aos);
+    let los = to_julian_date(fine.los);
+    assert!(el(&mut predict, aos) >= 0. && el(&mut predict, aos - CROSSING_PRECISION_DAYS) < 0.);
+    assert!(el(&mut predict, los) < 0. && el(&mut predict, los - CROSSING_PRECISION_DAYS) >= 0.);
+}
+
+#[test]
+fn predict_is_visible() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+
+    // the midday pass is in daylight, the evening one is seen in the dark
+    let noon = from_julian_date(2457266.81187298);
+    let evening = from_julian_date(2457267.264931);
+    predict.update_at(noon).unwrap();
+    let el = predict.sat.el_deg;
+
+    assert!(!predict.is_visible(noon));
+    assert!(predict.is_visible(evening));
+    assert!(!predict.is_visible(from_julian_date(2457267.3)));
+    assert_eq!(predict.sat.el_deg, el);
+
+    // just below the geometric horizon, refraction lifts it into view
+    let rising = from_julian_date(2457267.2611944);
+    assert!(predict.observe_at(rising).el_deg < 0.);
+    assert!(!predict.is_visible(rising));
+    predict.set_refraction(RefractionModel::Optical);
+    assert!(predict.is_visible(rising));
+}
+
+#[test]
+fn predict_deep_space() {
+    // GRIFEX slowed down to a geosynchronous mean motion
+    let (grifex, location) = ::grifex();
+    let tle = tle::Tle {
+        line2: "2 40379  99.1124 290.6779 0157088   8.9691 351.4280  1.00271000 31887".to_string(),
+        ..grifex
+    };
+    let mut predict = Predict::new(&tle, location).unwrap();
+    assert!(predict.is_deep_space());
+
+    let t = from_julian_date(2457266.5);
+    if cfg!(feature = "pure-rust") {
+        assert_eq!(predict.update_at(t), Err(PredictError::DeepSpaceUnsupported));
+    }
+    else {
+        assert_eq!(predict.update_at(t), Ok(()));
+    }
+}
+
+#[test]
+fn predict_max_tle_age() {
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+
+    // the epoch is 2015-08-31 10:14 UTC
+    let fresh = from_julian_date(2457266.5);
+    let stale = from_julian_date(2457270.5);
+    assert!(!predict.tle_is_stale(stale));
+    assert_eq!(predict.update_at(stale), Ok(()));
+
+    predict.set_max_tle_age(Some(Duration::from_secs(2 * 86400)));
+    assert!(!predict.tle_is_stale(fresh));
+    assert!(predict.tle_is_stale(stale));
+    assert_eq!(predict.update_at(fresh), Ok(()));
+    let el = predict.sat.el_deg;
+    assert_eq!(predict.update_at(stale), Err(PredictError::StaleTle(predict.tle_age(stale))));
+    assert_eq!(predict.sat.el_deg, el);
+
+    predict.set_max_tle_age(None);
+    assert_eq!(predict.update_at(stale), Ok(()));
 }
@@ -22,31 +22,57 @@
  * SOFTWARE.
  */
 
-use hifitime::Epoch;
+use std::fmt;
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use coordinates::LLA;
 
+use ::coords::{self, EarthModel, EARTH_GM_KM3_S2, EARTH_ROTATION_RAD_S};
+use ::sun;
+use ::location::Location;
+use ::clock::{self, to_julian_date};
 
-#[derive(Default, Debug)]
+/// speed of light [m/s]
+pub const SPEED_OF_LIGHT_M_S: f64 = 299_792_458.0;
+
+/// height of the thin shell ionosphere model [km]
+const IONOSPHERE_HEIGHT_KM: f64 = 350.0;
+
+/// typical magnetic field component along the path in the ionosphere [T]
+const IONOSPHERE_FIELD_T: f64 = 5e-5;
+
+/// Satellite seen from the observer at one point in time.
+///
+/// The fields are valid all along the orbit, not only during a pass. Below
+/// the horizon the elevation is negative while azimuth and range rate keep
+/// their meaning, e.g. for pointing the antenna at the AOS azimuth in advance.
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sat {
     /// next AOS
-    pub aos:                Option<Epoch>,
+    pub aos:                Option<DateTime<Utc>>,
 
     /// next LOS
-    pub los:                Option<Epoch>,
+    pub los:                Option<DateTime<Utc>>,
 
     /// azimuth [deg]
     pub az_deg:             f64,
 
-    /// elevation [deg]
+    /// elevation [deg], negative below the horizon
     pub el_deg:             f64,
 
+    /// azimuth rate [deg/s]
+    pub az_rate_deg_s:      f64,
+
+    /// elevation rate [deg/s]
+    pub el_rate_deg_s:      f64,
+
     /// range [km]
     pub range_km:           f64,
 
-    /// range rate [km/sec]
+    /// range rate [km/sec], negative while approaching
     pub range_rate_km_sec:  f64,
 
-    /// SSP latitude [deg]
+    /// SSP geodetic latitude [deg], see `subpoint_geodetic`
     pub lat_deg:            f64,
 
     /// SSP longitude [deg]
@@ -58,11 +84,69 @@ pub struct Sat {
     /// velocity [km/s]
     pub vel_km_s:           f64,
 
-    /// orbit number
+    /// orbit number, see `Predict::revolution_at`
     pub orbit_nr:           u64,
+
+    /// footprint diameter [km]
+    pub footprint_km:       f64,
+
+    /// mean anomaly [deg]
+    pub mean_anomaly_deg:   f64,
+
+    /// orbit phase [deg]
+    pub phase_deg:          f64,
+
+    /// topocentric right ascension [deg]
+    pub right_ascension_deg: f64,
+
+    /// topocentric declination [deg]
+    pub declination_deg:    f64,
+
+    /// ECI position [km]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) eci_pos_km:  [f64; 3],
+
+    /// ECI velocity [km/s]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) eci_vel_km_s: [f64; 3],
+
+    /// ECI position of the observer [km]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) observer_eci_km: [f64; 3],
+
+    /// range vector in the topocentric (south, east, zenith) frame [km]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) topocentric_km: [f64; 3],
+
+    /// ellipsoid for the helpers below, see `Predict::set_earth_model`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) earth:       EarthModel,
+}
+
+/// Boresight direction of an antenna fixed to the spacecraft body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntennaAxis {
+    /// pointing to the center of the earth
+    Nadir,
+    /// pointing along the velocity vector
+    Velocity,
 }
 
 impl Sat {
+    /// Creates a `Sat` from ECI state vectors [km, km/s] without running any
+    /// propagation, mainly for testing code built on `Sat`.
+    ///
+    /// All other fields are zero and `None`, they are public and can be set
+    /// directly.
+    pub fn from_vectors(eci_pos_km: [f64; 3], eci_vel_km_s: [f64; 3], observer_eci_km: [f64; 3]) -> Sat {
+        Sat {
+            eci_pos_km:         eci_pos_km,
+            eci_vel_km_s:       eci_vel_km_s,
+            observer_eci_km:    observer_eci_km,
+            ..Default::default()
+        }
+    }
+
     pub fn location<T: From<LLA>>(&self) -> T {
         LLA {
             lat_deg: self.lat_deg,
@@ -70,6 +154,296 @@ impl Sat {
             alt_m:   self.alt_km*1000.,
         }.into()
     }
+
+    /// AOS in the time zone `tz`, e.g. a `chrono::FixedOffset` or `chrono::Local`.
+    pub fn aos_local<Tz: TimeZone>(&self, tz: &Tz) -> Option<DateTime<Tz>> {
+        self.aos.map(|t| t.with_timezone(tz))
+    }
+
+    /// LOS in the time zone `tz`.
+    pub fn los_local<Tz: TimeZone>(&self, tz: &Tz) -> Option<DateTime<Tz>> {
+        self.los.map(|t| t.with_timezone(tz))
+    }
+
+    /// Time from `now` until AOS, negative if AOS is in the past.
+    ///
+    /// `None` if there is no AOS, e.g. for satellites which never rise.
+    pub fn time_until_aos(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.aos.map(|aos| aos - now)
+    }
+
+    /// Time from `now` until LOS, negative if LOS is in the past.
+    ///
+    /// `None` if there is no LOS, e.g. for satellites which never set.
+    pub fn time_until_los(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.los.map(|los| los - now)
+    }
+
+    /// Doppler shift [Hz] of a signal transmitted by the satellite at `base_frequency_hz`.
+    ///
+    /// Positive range rate (satellite receding) gives a negative shift, so the
+    /// received frequency is lower than the transmitted one.
+    pub fn doppler_shift_hz(&self, base_frequency_hz: f64) -> f64 {
+        -base_frequency_hz * (self.range_rate_km_sec * 1000.0 / SPEED_OF_LIGHT_M_S)
+    }
+
+    /// Rate of change of the Doppler shift [Hz/s] of a signal transmitted at
+    /// `base_frequency_hz`, e.g. for keeping an SDR locked around the culmination.
+    pub fn doppler_rate_hz_s(&self, base_frequency_hz: f64) -> f64 {
+        -base_frequency_hz * (self.range_accel_km_s2() * 1000.0 / SPEED_OF_LIGHT_M_S)
+    }
+
+    /// Range acceleration [km/s²], the rate of change of `range_rate_km_sec`.
+    ///
+    /// Central difference over one second of the range rate, with the
+    /// satellite moved on a two body orbit and the observer with the earth's
+    /// rotation.
+    pub fn range_accel_km_s2(&self) -> f64 {
+        let dt = 0.5;
+        (self.range_rate_after(dt) - self.range_rate_after(-dt)) / (2.0 * dt)
+    }
+
+    // range rate [km/s] `dt` seconds after this state
+    fn range_rate_after(&self, dt: f64) -> f64 {
+        let p = self.eci_pos_km;
+        let v = self.eci_vel_km_s;
+        let r = coords::magnitude(&p);
+        let k = -EARTH_GM_KM3_S2 / (r * r * r);
+
+        let (sin_t, cos_t) = (EARTH_ROTATION_RAD_S * dt).sin_cos();
+        let o = self.observer_eci_km;
+        let observer = [o[0] * cos_t - o[1] * sin_t, o[0] * sin_t + o[1] * cos_t, o[2]];
+
+        let mut range = [0.0; 3];
+        let mut range_vel = [0.0; 3];
+        let observer_vel = [-EARTH_ROTATION_RAD_S * observer[1], EARTH_ROTATION_RAD_S * observer[0], 0.0];
+        for i in 0..3 {
+            let a = k * p[i];
+            range[i] = p[i] + v[i] * dt + 0.5 * a * dt * dt - observer[i];
+            range_vel[i] = v[i] + a * dt - observer_vel[i];
+        }
+
+        (range[0] * range_vel[0] + range[1] * range_vel[1] + range[2] * range_vel[2]) / coords::magnitude(&range)
+    }
+
+    /// ECI position (x, y, z) [km]
+    pub fn eci_position_km(&self) -> [f64; 3] {
+        self.eci_pos_km
+    }
+
+    /// Range vector from the observer to the satellite as (south, east,
+    /// zenith) components [km], free of the az/el singularity at the zenith.
+    pub fn topocentric_vector_km(&self) -> [f64; 3] {
+        self.topocentric_km
+    }
+
+    /// ECEF position (x, y, z) [km] for the time `t` this `Sat` was computed for.
+    pub fn ecef_position_km(&self, t: DateTime<Utc>) -> [f64; 3] {
+        coords::eci_to_ecef(&self.eci_pos_km, to_julian_date(t))
+    }
+
+    /// ECI velocity (x, y, z) [km/s]
+    pub fn eci_velocity_km_s(&self) -> [f64; 3] {
+        self.eci_vel_km_s
+    }
+
+    /// Angle [deg] between the antenna boresight and the direction from the
+    /// satellite to the observer, 0° when the antenna points at the station.
+    pub fn squint_angle_deg(&self, antenna: AntennaAxis) -> f64 {
+        let p = self.eci_pos_km;
+        let to_observer = [self.observer_eci_km[0] - p[0],
+                           self.observer_eci_km[1] - p[1],
+                           self.observer_eci_km[2] - p[2]];
+        let boresight = match antenna {
+            AntennaAxis::Nadir => [-p[0], -p[1], -p[2]],
+            AntennaAxis::Velocity => self.eci_vel_km_s,
+        };
+
+        coords::angle(&boresight, &to_observer).to_degrees()
+    }
+
+    /// Returns `points` (lat_deg, lon_deg) points on the edge of the footprint,
+    /// e.g. to draw the area the satellite is visible from.
+    pub fn coverage_circle(&self, points: usize) -> Vec<(f64, f64)> {
+        // angular radius of the footprint
+        let d = self.footprint_km / 2.0 / self.earth.radius_km;
+
+        coords::small_circle(self.lat_deg, self.lon_deg, d, points)
+    }
+
+    /// Returns true if the satellite is illuminated by the sun at time `t`.
+    ///
+    /// `t` should be the time this `Sat` was computed for.
+    pub fn is_sunlit(&self, t: DateTime<Utc>) -> bool {
+        !self.is_eclipsed(t)
+    }
+
+    /// Returns true if the satellite is in the earth's shadow at time `t`.
+    pub fn is_eclipsed(&self, t: DateTime<Utc>) -> bool {
+        sun::is_eclipsed(&self.eci_pos_km, &sun::position(t))
+    }
+
+    /// Frequency [Hz] observed on the ground for a downlink at `base_frequency_hz`.
+    pub fn shifted_frequency_hz(&self, base_frequency_hz: f64) -> f64 {
+        base_frequency_hz + self.doppler_shift_hz(base_frequency_hz)
+    }
+
+    /// Frequency [Hz] to transmit on so that the satellite receives `nominal_uplink_hz`.
+    ///
+    /// For a linear transponder tune the uplink with this and the downlink
+    /// with `shifted_frequency_hz`. With an inverting transponder the downlink
+    /// moves opposite to the uplink, so compute the downlink nominal from the
+    /// nominal uplink first and only then apply `shifted_frequency_hz`.
+    pub fn uplink_frequency_hz(&self, nominal_uplink_hz: f64) -> f64 {
+        nominal_uplink_hz + nominal_uplink_hz * (self.range_rate_km_sec * 1000.0 / SPEED_OF_LIGHT_M_S)
+    }
+
+    /// Returns true if the satellite is above the geometric horizon, without
+    /// the AOS elevation, horizon mask or refraction of the `Predict`.
+    pub fn above_horizon(&self) -> bool {
+        self.el_deg > 0.0
+    }
+
+    /// Returns true if the satellite is moving north.
+    pub fn is_ascending(&self) -> bool {
+        coords::is_northbound(&self.eci_pos_km, &self.eci_vel_km_s)
+    }
+
+    /// Azimuth as one of the 16 compass points, e.g. "NNE".
+    pub fn az_cardinal(&self) -> &'static str {
+        const POINTS: [&str; 16] = ["N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+                                    "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW"];
+
+        let index = (self.az_deg.rem_euclid(360.0) / 22.5).round() as usize % 16;
+        POINTS[index]
+    }
+
+    /// Rough Faraday rotation [rad] of a signal at `frequency_hz` on the path
+    /// to the station, for a vertical total electron content `tec` [electrons/m²].
+    ///
+    /// The vertical TEC is scaled by the thin shell obliquity factor at the
+    /// current elevation and a typical mid-latitude field of 50 µT is assumed
+    /// along the path, as in ITU-R P.531.
+    pub fn faraday_rotation_rad(&self, frequency_hz: f64, tec: f64) -> f64 {
+        let radius_km = self.earth.radius_km;
+        let x = radius_km * self.el_deg.to_radians().cos() / (radius_km + IONOSPHERE_HEIGHT_KM);
+        let obliquity = 1.0 / (1.0 - x * x).sqrt();
+
+        2.36e4 * IONOSPHERE_FIELD_T * tec * obliquity / (frequency_hz * frequency_hz)
+    }
+
+    /// Geodetic (lat_deg, lon_deg) of the sub-satellite point, measured along
+    /// the ellipsoid normal. This is what `lat_deg` holds and what mapping
+    /// libraries expect.
+    pub fn subpoint_geodetic(&self) -> (f64, f64) {
+        (self.lat_deg, self.lon_deg)
+    }
+
+    /// Geocentric (lat_deg, lon_deg) of the sub-satellite point, measured along
+    /// the radius vector from the earth's centre. Differs from the geodetic
+    /// latitude by up to ~0.2° at mid latitudes.
+    pub fn subpoint_geocentric(&self) -> (f64, f64) {
+        let p = self.eci_pos_km;
+        let lat = p[2].atan2((p[0] * p[0] + p[1] * p[1]).sqrt());
+
+        (lat.to_degrees(), self.lon_deg)
+    }
+
+    /// Slant path [km] through an atmosphere of `scale_height_km` thickness
+    /// towards the satellite, e.g. for gas or rain attenuation models.
+    ///
+    /// This is `scale_height_km / sin(el)` for high elevations, near the
+    /// horizon the curvature of the shell keeps it finite. Elevations below
+    /// the horizon are treated as 0°.
+    pub fn atmospheric_path_length_km(&self, scale_height_km: f64) -> f64 {
+        let el = self.el_deg.max(0.0).to_radians();
+        let r = self.earth.radius_km;
+        let top = r + scale_height_km;
+
+        (top * top - (r * el.cos()).powi(2)).sqrt() - r * el.sin()
+    }
+
+    /// Great circle distance [km] from `observer` to the sub-satellite point.
+    pub fn ground_distance_km(&self, observer: &Location) -> f64 {
+        self.distance_to_subpoint_km(observer.lat_deg, observer.lon_deg)
+    }
+
+    /// Returns true if the ground point at `lat`, `lon` [deg] is inside the
+    /// footprint, i.e. the satellite is above its geometric horizon.
+    pub fn covers_point(&self, lat: f64, lon: f64) -> bool {
+        self.distance_to_subpoint_km(lat, lon) <= self.footprint_km / 2.0
+    }
+
+    // haversine distance [km] from lat_deg, lon_deg to the sub-satellite point
+    fn distance_to_subpoint_km(&self, lat_deg: f64, lon_deg: f64) -> f64 {
+        let lat1 = lat_deg.to_radians();
+        let lat2 = self.lat_deg.to_radians();
+        let dlat = lat2 - lat1;
+        let dlon = (self.lon_deg - lon_deg).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * self.earth.radius_km * a.sqrt().min(1.0).asin()
+    }
+
+    /// One-way signal propagation delay [ms] between station and satellite.
+    pub fn propagation_delay_ms(&self) -> f64 {
+        self.range_km * 1000.0 / SPEED_OF_LIGHT_M_S * 1000.0
+    }
+
+    /// Two-way light time [ms], e.g. for a command and its acknowledgement.
+    pub fn round_trip_delay_ms(&self) -> f64 {
+        2.0 * self.propagation_delay_ms()
+    }
+
+    /// Current look angles and sub-satellite point as a single line JSON
+    /// object, e.g. for streaming NDJSON to other tools.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let json = SatJson {
+            az_deg:             self.az_deg,
+            el_deg:             self.el_deg,
+            range_km:           self.range_km,
+            range_rate_km_sec:  self.range_rate_km_sec,
+            lat_deg:            self.lat_deg,
+            lon_deg:            self.lon_deg,
+            alt_km:             self.alt_km,
+        };
+
+        // plain f64 fields, serialization can't fail (non finite values become null)
+        serde_json::to_string(&json).expect("sat json")
+    }
+
+    /// One line summary of the current look angles for logging.
+    pub fn summary_line(&self) -> String {
+        format!("az {:.2}° el {:.2}° range {:.0} km range rate {:.3} km/sec",
+                self.az_deg, self.el_deg, self.range_km, self.range_rate_km_sec)
+    }
+}
+
+/// stable field layout of `Sat::to_json`
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct SatJson {
+    az_deg:             f64,
+    el_deg:             f64,
+    range_km:           f64,
+    range_rate_km_sec:  f64,
+    lat_deg:            f64,
+    lon_deg:            f64,
+    alt_km:             f64,
+}
+
+impl fmt::Display for Sat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let epoch = |e: Option<DateTime<Utc>>| e.map_or("-".to_string(), |e| clock::format_local(e, &Utc));
+
+        writeln!(f, "aos        : {}", epoch(self.aos))?;
+        writeln!(f, "los        : {}", epoch(self.los))?;
+        writeln!(f, "az         : {:.2}°", self.az_deg)?;
+        writeln!(f, "el         : {:.2}°", self.el_deg)?;
+        writeln!(f, "range      : {:.0} km", self.range_km)?;
+        write!(f, "range rate : {:.3} km/sec", self.range_rate_km_sec)
+    }
 }
 
 #[test]
@@ -79,3 +453,186 @@ fn sat_location_formats() {
     let _lla:  LLA  = sat.location();
     let _ecef: ECEF = sat.location();
 }
+
+#[test]
+fn sat_doppler_sign() {
+    let mut sat = Sat::default();
+    sat.range_rate_km_sec = 5.;
+    assert!(sat.doppler_shift_hz(437e6) < 0.);
+    assert!(sat.shifted_frequency_hz(437e6) < 437e6);
+
+    sat.range_rate_km_sec = -5.;
+    assert!(sat.shifted_frequency_hz(437e6) > 437e6);
+    assert!(sat.uplink_frequency_hz(145e6) < 145e6);
+}
+
+#[test]
+fn sat_az_cardinal() {
+    let mut sat = Sat::default();
+    for &(az, point) in [(0., "N"), (11., "N"), (12., "NNE"), (100., "E"), (350., "N"), (-30., "NNW")].iter() {
+        sat.az_deg = az;
+        assert_eq!(sat.az_cardinal(), point);
+    }
+}
+
+#[test]
+fn sat_faraday_rotation() {
+    let mut sat = Sat::default();
+    sat.el_deg = 90.;
+    let zenith = sat.faraday_rotation_rad(1e9, 1e18);
+    assert!((zenith - 1.18).abs() < 1e-9);
+
+    // longer path at low elevation, less rotation at higher frequencies
+    sat.el_deg = 10.;
+    assert!(sat.faraday_rotation_rad(1e9, 1e18) > 2. * zenith);
+    assert!(sat.faraday_rotation_rad(2e9, 1e18) < zenith);
+}
+
+#[test]
+fn sat_ground_distance() {
+    let mut sat = Sat::default();
+    sat.lon_deg = 90.;
+    let observer = Location { lat_deg: 0., lon_deg: 0., alt_m: 0. };
+
+    assert!((sat.ground_distance_km(&observer) - coords::EARTH_RADIUS_KM * std::f64::consts::PI / 2.).abs() < 1e-6);
+
+    // footprint of 1000 km radius centred at 0°N 90°E, 1° is about 111 km
+    sat.footprint_km = 2000.;
+    assert!(sat.covers_point(0., 90.));
+    assert!(sat.covers_point(7., 86.));
+    assert!(!sat.covers_point(0., 99.5));
+    assert!(!sat.covers_point(observer.lat_deg, observer.lon_deg));
+}
+
+#[test]
+fn sat_range_accel() {
+    // overhead, moving east faster than the observer
+    let r = coords::EARTH_RADIUS_KM;
+    let sat = Sat::from_vectors([r + 500., 0., 0.], [0., 7.6, 0.], [r, 0., 0.]);
+
+    // (|v|² + ρ·a - ρ̇²) / |ρ| with ρ̇ = 0
+    let v = 7.6 - EARTH_ROTATION_RAD_S * r;
+    let a = -EARTH_GM_KM3_S2 / ((r + 500.) * (r + 500.)) + EARTH_ROTATION_RAD_S * EARTH_ROTATION_RAD_S * r;
+    let expected = (v * v + 500. * a) / 500.;
+
+    assert!((sat.range_accel_km_s2() - expected).abs() < 1e-4);
+    assert!(sat.doppler_rate_hz_s(437e6) < 0.);
+}
+
+#[test]
+fn sat_propagation_delay() {
+    let mut sat = Sat::default();
+    sat.range_km = 299_792.458;
+    assert!((sat.propagation_delay_ms() - 1000.).abs() < 1e-9);
+    assert!((sat.round_trip_delay_ms() - 2000.).abs() < 1e-9);
+}
+
+#[test]
+fn sat_display() {
+    let mut sat = Sat::default();
+    sat.az_deg = 12.345;
+    sat.range_km = 500.2;
+
+    let text = sat.to_string();
+    assert!(text.starts_with("aos        : -\n"));
+    assert!(text.contains("az         : 12.35°\n"));
+    assert!(text.ends_with("range rate : 0.000 km/sec"));
+    assert_eq!(sat.summary_line(), "az 12.35° el 0.00° range 500 km range rate 0.000 km/sec");
+}
+
+#[test]
+fn sat_aos_local() {
+    use chrono::FixedOffset;
+    use ::clock::from_julian_date;
+
+    let mut sat = Sat::default();
+    let tz = FixedOffset::east_opt(3 * 3600).unwrap();
+    assert_eq!(sat.aos_local(&tz), None);
+
+    sat.aos = Some(from_julian_date(2457266.5));
+    let aos = sat.aos_local(&tz).unwrap();
+    assert_eq!(aos, sat.aos.unwrap());
+    assert_eq!(aos.offset().local_minus_utc(), 3 * 3600);
+    assert_eq!(sat.los_local(&tz), None);
+}
+
+#[test]
+fn sat_time_until() {
+    use ::clock::from_julian_date;
+
+    let mut sat = Sat::default();
+    let now = from_julian_date(2457266.5);
+    assert_eq!(sat.time_until_aos(now), None);
+
+    sat.aos = Some(now + Duration::seconds(90));
+    sat.los = Some(now - Duration::milliseconds(1500));
+    assert_eq!(sat.time_until_aos(now), Some(Duration::seconds(90)));
+    assert_eq!(sat.time_until_los(now), Some(Duration::milliseconds(-1500)));
+}
+
+#[test]
+fn sat_squint_angle() {
+    use ::clock::from_julian_date;
+
+    // station right below the satellite
+    let sat = Sat::from_vectors([7000., 0., 0.], [0., 7.5, 0.], [6378., 0., 0.]);
+    assert!(!sat.is_ascending());
+    let ecef = sat.ecef_position_km(from_julian_date(2457266.5));
+    assert!((coords::magnitude(&ecef) - 7000.).abs() < 1e-9 && ecef[2] == 0.);
+    assert!(sat.squint_angle_deg(AntennaAxis::Nadir).abs() < 1e-9);
+    assert!((sat.squint_angle_deg(AntennaAxis::Velocity) - 90.).abs() < 1e-9);
+}
+
+#[test]
+fn sat_subpoint() {
+    let mut sat = Sat::from_vectors([5000., 0., 5000.], [0., 7.5, 0.], [6378., 0., 0.]);
+    let (lat, _, _) = sat.earth.ecef_to_geodetic(&sat.eci_pos_km);
+    sat.lat_deg = lat;
+    sat.lon_deg = 12.;
+
+    let (geocentric_lat, lon) = sat.subpoint_geocentric();
+    assert!((geocentric_lat - 45.).abs() < 1e-9 && lon == 12.);
+    assert_eq!(sat.subpoint_geodetic(), (lat, 12.));
+    assert!(lat - geocentric_lat > 0.1 && lat - geocentric_lat < 0.2);
+}
+
+#[test]
+fn sat_atmospheric_path_length() {
+    let mut sat = Sat::default();
+    sat.el_deg = 90.;
+    assert!((sat.atmospheric_path_length_km(8.) - 8.).abs() < 1e-9);
+
+    sat.el_deg = 30.;
+    let flat = 8. / 30f64.to_radians().sin();
+    let path = sat.atmospheric_path_length_km(8.);
+    assert!(path < flat && flat - path < 0.1);
+
+    sat.el_deg = -1.;
+    let r = sat.earth.radius_km;
+    assert!((sat.atmospheric_path_length_km(8.) - (2. * r * 8. + 64f64).sqrt()).abs() < 1e-6);
+}
+
+#[test]
+fn sat_below_horizon() {
+    use ::predict::Predict;
+    use ::clock::from_julian_date;
+
+    let (tle, observer) = ::grifex();
+    let mut predict = Predict::new(&tle, observer).unwrap();
+    let pass = predict.next_pass(from_julian_date(2457266.5)).unwrap();
+    let minute = 1. / 1440.;
+
+    // approaching before AOS, close to the AOS azimuth
+    let before = predict.observe_at(from_julian_date(to_julian_date(pass.aos) - minute));
+    assert!(!before.above_horizon() && before.el_deg > -10.);
+    assert!(before.range_rate_km_sec < 0.);
+    assert!((before.az_deg - pass.aos_azimuth_deg).abs() < 10.);
+
+    let during = predict.observe_at(pass.culmination);
+    assert!(during.above_horizon());
+
+    // receding after LOS
+    let after = predict.observe_at(from_julian_date(to_julian_date(pass.los) + minute));
+    assert!(!after.above_horizon() && after.range_rate_km_sec > 0.);
+    assert!((after.az_deg - pass.los_azimuth_deg).abs() < 10.);
+}
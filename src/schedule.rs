@@ -0,0 +1,75 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use ::pass::Pass;
+
+/// Index pairs `(i, j)`, `i < j`, of passes whose [aos, los] intervals
+/// overlap, e.g. passes of different satellites competing for one rotator.
+///
+/// Passes touching at a single instant do not conflict. The pairs are sorted.
+pub fn conflicts(passes: &[Pass]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..passes.len()).collect();
+    order.sort_by_key(|&i| passes[i].aos);
+
+    // sweep in AOS order, every pass only needs to be compared with the
+    // following ones which start before it ends
+    let mut pairs = Vec::new();
+    for (n, &i) in order.iter().enumerate() {
+        let los = passes[i].los;
+        for &j in order[n + 1..].iter() {
+            if passes[j].aos >= los {
+                break;
+            }
+            pairs.push((i.min(j), i.max(j)));
+        }
+    }
+
+    pairs.sort();
+    pairs
+}
+
+#[test]
+fn schedule_conflicts() {
+    use ::clock::from_julian_date;
+
+    let pass = |aos: f64, los: f64| Pass {
+        aos:                from_julian_date(aos),
+        los:                from_julian_date(los),
+        culmination:        from_julian_date((aos + los) / 2.),
+        max_elevation_deg:  45.,
+        aos_azimuth_deg:    0.,
+        los_azimuth_deg:    180.,
+        duration_secs:      ((los - aos) * 86400.) as i64,
+        ascending:          true,
+    };
+
+    let passes = [pass(2457266.50, 2457266.51),
+                  pass(2457266.60, 2457266.61),
+                  pass(2457266.505, 2457266.52),
+                  pass(2457266.51, 2457266.515),
+                  pass(2457266.40, 2457266.70)];
+
+    assert_eq!(conflicts(&passes), vec![(0, 2), (0, 4), (1, 4), (2, 3), (2, 4), (3, 4)]);
+    assert!(conflicts(&[]).is_empty());
+}
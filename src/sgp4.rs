@@ -0,0 +1,631 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+// Native SGP4 backend used instead of libgpredict with the `pure-rust`
+// feature. The functions mirror the libgpredict ones declared in ffipredict
+// and are ported from gpredict's sgp4sdp4.c and predict-tools.c. The deep
+// space SDP4 model is not implemented, satellites which need it propagate to
+// NaN and never have AOS/LOS.
+
+#![allow(non_snake_case)]
+
+use std::f64::consts::PI;
+use std::ffi::CStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use libc::{c_char, c_double, c_int, c_ulong};
+
+use ffipredict::{sat_t, qth_t, tle_t, orbit_type_t, DEEP_SPACE_EPHEM_FLAG, SGP4_INITIALIZED_FLAG};
+use coords::{self, EARTH_RADIUS_KM, EARTH_FLATTENING, CK2, XKE};
+use tle::{self, Tle};
+use predict;
+
+const TWOPI: f64 = 2.0 * PI;
+const AE: f64 = 1.0;
+const TOTHRD: f64 = 2.0 / 3.0;
+const XKMPER: f64 = EARTH_RADIUS_KM;
+const CK4: f64 = 6.209887e-7;
+const XJ3: f64 = -2.53881e-6;
+const QOMS2T: f64 = 1.880279e-09;
+const S: f64 = 1.012229;
+const E6A: f64 = 1.0e-6;
+const XMNPDA: f64 = 1440.0;
+const SECDAY: f64 = 86400.0;
+const MFACTOR: f64 = coords::EARTH_ROTATION_RAD_S;
+
+const SIMPLE_FLAG: c_int = 0x000020;
+
+pub unsafe fn get_current_daynum() -> c_double {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    now.as_secs_f64() / SECDAY + 2440587.5
+}
+
+pub unsafe fn predict_calc(sat: *mut sat_t, qth: *mut qth_t, t: c_double) {
+    calc(&mut *sat, &*qth, t);
+}
+
+pub unsafe fn find_aos(sat: *mut sat_t, qth: *mut qth_t, start: c_double, maxdt: c_double) -> c_double {
+    aos(&mut *sat, &*qth, start, maxdt)
+}
+
+pub unsafe fn find_los(sat: *mut sat_t, qth: *mut qth_t, start: c_double, maxdt: c_double) -> c_double {
+    los(&mut *sat, &*qth, start, maxdt)
+}
+
+/// Parses the name, line 1 and line 2 stored as C strings in three 80 byte
+/// rows starting at `line`. Returns 1 on success like libgpredict.
+pub unsafe fn Get_Next_Tle_Set(line: *const c_char, tle: *mut tle_t) -> c_int {
+    let row = |i: isize| CStr::from_ptr(line.offset(80 * i)).to_str().ok();
+
+    match (row(0), row(1), row(2)) {
+        (Some(name), Some(line1), Some(line2)) if line1.is_ascii() && line2.is_ascii() => {
+            match Tle::parse(name, line1, line2) {
+                Ok(parsed) => {
+                    convert_satellite_data(&parsed, &mut *tle);
+                    1
+                }
+                Err(_) => -2,
+            }
+        }
+        _ => -2,
+    }
+}
+
+pub unsafe fn select_ephemeris(sat: *mut sat_t) {
+    let sat = &mut *sat;
+
+    // preprocess tle set
+    sat.tle.xnodeo = sat.tle.xnodeo.to_radians();
+    sat.tle.omegao = sat.tle.omegao.to_radians();
+    sat.tle.xmo = sat.tle.xmo.to_radians();
+    sat.tle.xincl = sat.tle.xincl.to_radians();
+    let temp = TWOPI / XMNPDA / XMNPDA;
+
+    // store mean motion before conversion
+    sat.meanmo = sat.tle.xno;
+    sat.tle.xno = sat.tle.xno * temp * XMNPDA;
+    sat.tle.xndt2o *= temp;
+    sat.tle.xndd6o = sat.tle.xndd6o * temp / XMNPDA;
+    sat.tle.bstar /= AE;
+
+    // period > 225 minutes is deep space
+    let a1 = (XKE / sat.tle.xno).powf(TOTHRD);
+    let r1 = sat.tle.xincl.cos();
+    let dd1 = 1.0 - sat.tle.eo * sat.tle.eo;
+    let temp = CK2 * 1.5 * (r1 * r1 * 3.0 - 1.0) / dd1.powf(1.5);
+    let del1 = temp / (a1 * a1);
+    let ao = a1 * (1.0 - del1 * (TOTHRD * 0.5 + del1 * (del1 * 1.654320987654321 + 1.0)));
+    let delo = temp / (ao * ao);
+    let xnodp = sat.tle.xno / (delo + 1.0);
+
+    if TWOPI / xnodp / XMNPDA >= 0.15625 {
+        sat.flags |= DEEP_SPACE_EPHEM_FLAG;
+    }
+    else {
+        sat.flags &= !DEEP_SPACE_EPHEM_FLAG;
+    }
+}
+
+pub unsafe fn gtk_sat_data_init_sat(sat: *mut sat_t, qth: *mut qth_t) {
+    let sat = &mut *sat;
+
+    sat.jul_epoch = tle::julian_date_of_epoch(sat.tle.epoch);
+    calc(sat, &*qth, sat.jul_epoch);
+    sat.otype = orbit_type(sat);
+}
+
+fn calc(sat: &mut sat_t, qth: &qth_t, t: f64) {
+    sat.jul_utc = t;
+    sat.tsince = (sat.jul_utc - sat.jul_epoch) * XMNPDA;
+
+    if sat.flags & DEEP_SPACE_EPHEM_FLAG != 0 {
+        sat.pos.x = ::std::f64::NAN;
+        sat.pos.y = ::std::f64::NAN;
+        sat.pos.z = ::std::f64::NAN;
+        sat.vel.x = ::std::f64::NAN;
+        sat.vel.y = ::std::f64::NAN;
+        sat.vel.z = ::std::f64::NAN;
+        sat.phase = ::std::f64::NAN;
+    }
+    else {
+        let tsince = sat.tsince;
+        sgp4(sat, tsince);
+    }
+
+    // scale position and velocity to km and km/sec
+    sat.pos.x *= XKMPER / AE;
+    sat.pos.y *= XKMPER / AE;
+    sat.pos.z *= XKMPER / AE;
+    sat.vel.x *= XKMPER / AE * XMNPDA / SECDAY;
+    sat.vel.y *= XKMPER / AE * XMNPDA / SECDAY;
+    sat.vel.z *= XKMPER / AE * XMNPDA / SECDAY;
+
+    let pos = [sat.pos.x, sat.pos.y, sat.pos.z];
+    let vel = [sat.vel.x, sat.vel.y, sat.vel.z];
+    sat.pos.w = coords::magnitude(&pos);
+    sat.vel.w = coords::magnitude(&vel);
+    sat.velo = sat.vel.w;
+
+    let alt_km = qth.alt as f64 / 1000.0;
+    let (az, el, range) = coords::look_angles(qth.lat, qth.lon, alt_km, &pos, t);
+
+    // observer velocity due to earth rotation
+    let obs = coords::geodetic_to_eci(qth.lat, qth.lon, alt_km, t);
+    let obs_vel = [-MFACTOR * obs[1], MFACTOR * obs[0], 0.0];
+    let range_rate = ((pos[0] - obs[0]) * (vel[0] - obs_vel[0])
+                      + (pos[1] - obs[1]) * (vel[1] - obs_vel[1])
+                      + (pos[2] - obs[2]) * (vel[2] - obs_vel[2])) / range;
+
+    let (lat, mut lon, alt) = lat_lon_alt(t, &pos);
+    while lon < -PI {
+        lon += TWOPI;
+    }
+    while lon > PI {
+        lon -= TWOPI;
+    }
+
+    sat.az = az;
+    sat.el = el;
+    sat.range = range;
+    sat.range_rate = range_rate;
+    sat.ssplat = lat.to_degrees();
+    sat.ssplon = lon.to_degrees();
+    sat.alt = alt;
+    sat.ma = sat.phase.to_degrees() * 256.0 / 360.0;
+    sat.phase = sat.phase.to_degrees();
+    sat.footprint = 12756.33 * (XKMPER / (XKMPER + sat.alt)).acos();
+
+    let age = sat.jul_utc - sat.jul_epoch;
+    let orbit = ((sat.tle.xno * XMNPDA / TWOPI + age * sat.tle.bstar * AE) * age
+                 + sat.tle.xmo / TWOPI).floor() as i64 + sat.tle.revnum as i64 - 1;
+    sat.orbit = orbit as c_ulong;
+}
+
+fn aos(sat: &mut sat_t, qth: &qth_t, start: f64, maxdt: f64) -> f64 {
+    let mut t = start;
+    let mut aostime = 0.0;
+
+    calc(sat, qth, start);
+
+    if !has_aos(sat, qth) {
+        return 0.0;
+    }
+
+    if sat.el > 0.0 {
+        // +20 min
+        t = los(sat, qth, start, maxdt) + 0.014;
+    }
+
+    // invalid time (potentially returned by los)
+    if t < 0.1 {
+        return 0.0;
+    }
+
+    calc(sat, qth, t);
+
+    let in_window = |t: f64| maxdt <= 0.0 || t <= start + maxdt;
+
+    // coarse time steps
+    while sat.el < -1.0 && in_window(t) {
+        t -= 0.00035 * (sat.el * ((sat.alt / 8400.0) + 0.46) - 2.0);
+        calc(sat, qth, t);
+    }
+
+    // fine steps
+    while aostime == 0.0 && in_window(t) {
+        if sat.el.abs() < 0.005 {
+            aostime = t;
+        }
+        else {
+            t -= sat.el * sat.alt.sqrt() / 530000.0;
+            calc(sat, qth, t);
+        }
+    }
+
+    aostime
+}
+
+fn los(sat: &mut sat_t, qth: &qth_t, start: f64, maxdt: f64) -> f64 {
+    let mut t = start;
+    let mut lostime = 0.0;
+
+    calc(sat, qth, start);
+
+    if !has_aos(sat, qth) {
+        return 0.0;
+    }
+
+    if sat.el < 0.0 {
+        // +1.5 min
+        t = aos(sat, qth, start, maxdt) + 0.001;
+    }
+
+    // invalid time (potentially returned by aos)
+    if t < 0.01 {
+        return 0.0;
+    }
+
+    calc(sat, qth, t);
+
+    let in_window = |t: f64| maxdt <= 0.0 || t <= start + maxdt;
+
+    // coarse steps
+    while sat.el >= 1.0 && in_window(t) {
+        t += ((sat.el - 1.0).to_radians()).cos() * sat.alt.sqrt() / 25000.0;
+        calc(sat, qth, t);
+    }
+
+    // fine steps
+    while lostime == 0.0 && in_window(t) {
+        t += sat.el * sat.alt.sqrt() / 502500.0;
+        calc(sat, qth, t);
+        if sat.el.abs() < 0.005 {
+            lostime = t;
+        }
+    }
+
+    lostime
+}
+
+// whether the satellite can ever rise above the observer's horizon
+fn has_aos(sat: &sat_t, qth: &qth_t) -> bool {
+    match sat.otype {
+        orbit_type_t::ORBIT_TYPE_GEO | orbit_type_t::ORBIT_TYPE_DECAYED => return false,
+        _ => {}
+    }
+
+    if sat.flags & DEEP_SPACE_EPHEM_FLAG != 0 {
+        return false;
+    }
+
+    predict::can_rise(sat, qth)
+}
+
+fn orbit_type(sat: &sat_t) -> orbit_type_t {
+    // xndt2o/(twopi/xmnpda/xmnpda) is the first derivative of the mean motion before conversion
+    let xndt2o = sat.tle.xndt2o / (TWOPI / XMNPDA / XMNPDA);
+
+    if (sat.meanmo - 1.0027).abs() < 0.0002 {
+        orbit_type_t::ORBIT_TYPE_GEO
+    }
+    else if sat.jul_epoch + (16.666666 - sat.meanmo) / (10.0 * xndt2o.abs()) < sat.jul_utc {
+        orbit_type_t::ORBIT_TYPE_DECAYED
+    }
+    else {
+        orbit_type_t::ORBIT_TYPE_UNKNOWN
+    }
+}
+
+// geodetic latitude [rad], longitude [rad] and altitude [km] of an ECI position
+fn lat_lon_alt(jd: f64, pos: &[f64; 3]) -> (f64, f64, f64) {
+    let theta = pos[1].atan2(pos[0]);
+    let lon = (theta - coords::gmst_jd(jd)).rem_euclid(TWOPI);
+    let r = (pos[0] * pos[0] + pos[1] * pos[1]).sqrt();
+    let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+
+    let mut lat = pos[2].atan2(r);
+    let mut c;
+    loop {
+        let phi = lat;
+        c = 1.0 / (1.0 - e2 * phi.sin() * phi.sin()).sqrt();
+        lat = (pos[2] + XKMPER * c * e2 * phi.sin()).atan2(r);
+        if (lat - phi).abs() < 1e-10 || lat.is_nan() {
+            break;
+        }
+    }
+
+    (lat, lon, r / lat.cos() - XKMPER * c)
+}
+
+fn convert_satellite_data(tle: &Tle, tle_t: &mut tle_t) {
+    let l1 = &tle.line1;
+    let l2 = &tle.line2;
+
+    for (i, b) in tle.name.bytes().take(24).enumerate() {
+        tle_t.sat_name[i] = b as c_char;
+        tle_t.sat_name[i + 1] = 0;
+    }
+    for (i, b) in l1[9..17].bytes().enumerate() {
+        tle_t.idesg[i] = b as c_char;
+        tle_t.idesg[i + 1] = 0;
+    }
+
+    tle_t.catnr = field(&l1[2..7]) as c_int;
+    tle_t.epoch = field(&l1[18..32]);
+    let year = field(&l1[18..20]) as u32;
+    tle_t.epoch_year = if year > 56 { year + 1900 } else { year + 2000 };
+    tle_t.epoch_day = field(&l1[20..23]) as u32;
+    tle_t.epoch_fod = field(&l1[23..32]);
+    tle_t.xndt2o = field(&l1[33..43]);
+    tle_t.xndd6o = exp_field(&l1[44..52]);
+    tle_t.bstar = exp_field(&l1[53..61]);
+    tle_t.elset = field(&l1[64..68]) as c_int;
+
+    tle_t.xincl = field(&l2[8..16]);
+    tle_t.xnodeo = field(&l2[17..25]);
+    tle_t.eo = field(&format!("0.{}", &l2[26..33]));
+    tle_t.omegao = field(&l2[34..42]);
+    tle_t.xmo = field(&l2[43..51]);
+    tle_t.xno = field(&l2[52..63]);
+    tle_t.revnum = field(&l2[63..68]) as c_int;
+}
+
+fn field(s: &str) -> f64 {
+    s.trim().parse().unwrap_or(0.0)
+}
+
+// number with implied leading decimal point and exponent, e.g. "-11606-4" = -0.11606e-4
+fn exp_field(s: &str) -> f64 {
+    let s = s.trim();
+    if s.len() < 2 {
+        return 0.0;
+    }
+
+    let (mantissa, exponent) = s.split_at(s.len() - 2);
+    let (sign, digits) = match mantissa.chars().next() {
+        Some('-') => ("-", &mantissa[1..]),
+        Some('+') => ("", &mantissa[1..]),
+        _ => ("", mantissa),
+    };
+
+    field(&format!("{}0.{}e{}", sign, digits, exponent))
+}
+
+fn sgp4(sat: &mut sat_t, tsince: f64) {
+    let tle = sat.tle.clone();
+
+    // initialization
+    if sat.flags & SGP4_INITIALIZED_FLAG == 0 {
+        sat.flags |= SGP4_INITIALIZED_FLAG;
+        let sgps = &mut sat.sgps;
+
+        // recover original mean motion (xnodp) and semimajor axis (aodp) from input elements
+        let a1 = (XKE / tle.xno).powf(TOTHRD);
+        sgps.cosio = tle.xincl.cos();
+        let theta2 = sgps.cosio * sgps.cosio;
+        sgps.x3thm1 = 3.0 * theta2 - 1.0;
+        let eosq = tle.eo * tle.eo;
+        let betao2 = 1.0 - eosq;
+        let betao = betao2.sqrt();
+        let del1 = 1.5 * CK2 * sgps.x3thm1 / (a1 * a1 * betao * betao2);
+        let ao = a1 * (1.0 - del1 * (0.5 * TOTHRD + del1 * (1.0 + 134.0 / 81.0 * del1)));
+        let delo = 1.5 * CK2 * sgps.x3thm1 / (ao * ao * betao * betao2);
+        sgps.xnodp = tle.xno / (1.0 + delo);
+        sgps.aodp = ao / (1.0 - delo);
+
+        // for perigee less than 220 km the equations are truncated to linear
+        // variation in sqrt a and quadratic variation in mean anomaly
+        let simple = sgps.aodp * (1.0 - tle.eo) / AE < 220.0 / XKMPER + AE;
+
+        // for perigee below 156 km the values of s and qoms2t are altered
+        let mut s4 = S;
+        let mut qoms24 = QOMS2T;
+        let perigee = (sgps.aodp * (1.0 - tle.eo) - AE) * XKMPER;
+        if perigee < 156.0 {
+            s4 = if perigee <= 98.0 { 20.0 } else { perigee - 78.0 };
+            qoms24 = ((120.0 - s4) * AE / XKMPER).powi(4);
+            s4 = s4 / XKMPER + AE;
+        }
+
+        let pinvsq = 1.0 / (sgps.aodp * sgps.aodp * betao2 * betao2);
+        let tsi = 1.0 / (sgps.aodp - s4);
+        sgps.eta = sgps.aodp * tle.eo * tsi;
+        let etasq = sgps.eta * sgps.eta;
+        let eeta = tle.eo * sgps.eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qoms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let c2 = coef1 * sgps.xnodp * (sgps.aodp * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                 + 0.75 * CK2 * tsi / psisq * sgps.x3thm1 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        sgps.c1 = c2 * tle.bstar;
+        sgps.sinio = tle.xincl.sin();
+        let a3ovk2 = -XJ3 / CK2 * AE.powi(3);
+        let c3 = coef * tsi * a3ovk2 * sgps.xnodp * AE * sgps.sinio / tle.eo;
+        sgps.x1mth2 = 1.0 - theta2;
+        sgps.c4 = 2.0 * sgps.xnodp * coef1 * sgps.aodp * betao2
+                  * (sgps.eta * (2.0 + 0.5 * etasq) + tle.eo * (0.5 + 2.0 * etasq)
+                     - 2.0 * CK2 * tsi / (sgps.aodp * psisq)
+                       * (-3.0 * sgps.x3thm1 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                          + 0.75 * sgps.x1mth2 * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * tle.omegao).cos()));
+        sgps.c5 = 2.0 * coef1 * sgps.aodp * betao2 * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+        let theta4 = theta2 * theta2;
+        let temp1 = 3.0 * CK2 * pinvsq * sgps.xnodp;
+        let temp2 = temp1 * CK2 * pinvsq;
+        let temp3 = 1.25 * CK4 * pinvsq * pinvsq * sgps.xnodp;
+        sgps.xmdot = sgps.xnodp + 0.5 * temp1 * betao * sgps.x3thm1
+                     + 0.0625 * temp2 * betao * (13.0 - 78.0 * theta2 + 137.0 * theta4);
+        let x1m5th = 1.0 - 5.0 * theta2;
+        sgps.omgdot = -0.5 * temp1 * x1m5th + 0.0625 * temp2 * (7.0 - 114.0 * theta2 + 395.0 * theta4)
+                      + temp3 * (3.0 - 36.0 * theta2 + 49.0 * theta4);
+        let xhdot1 = -temp1 * sgps.cosio;
+        sgps.xnodot = xhdot1 + (0.5 * temp2 * (4.0 - 19.0 * theta2)
+                                + 2.0 * temp3 * (3.0 - 7.0 * theta2)) * sgps.cosio;
+        sgps.omgcof = tle.bstar * c3 * tle.omegao.cos();
+        sgps.xmcof = -TOTHRD * coef * tle.bstar * AE / eeta;
+        sgps.xnodcf = 3.5 * betao2 * xhdot1 * sgps.c1;
+        sgps.t2cof = 1.5 * sgps.c1;
+        sgps.xlcof = 0.125 * a3ovk2 * sgps.sinio * (3.0 + 5.0 * sgps.cosio) / (1.0 + sgps.cosio);
+        sgps.aycof = 0.25 * a3ovk2 * sgps.sinio;
+        sgps.delmo = (1.0 + sgps.eta * tle.xmo.cos()).powi(3);
+        sgps.sinmo = tle.xmo.sin();
+        sgps.x7thm1 = 7.0 * theta2 - 1.0;
+
+        if !simple {
+            let c1sq = sgps.c1 * sgps.c1;
+            sgps.d2 = 4.0 * sgps.aodp * tsi * c1sq;
+            let temp = sgps.d2 * tsi * sgps.c1 / 3.0;
+            sgps.d3 = (17.0 * sgps.aodp + s4) * temp;
+            sgps.d4 = 0.5 * temp * sgps.aodp * tsi * (221.0 * sgps.aodp + 31.0 * s4) * sgps.c1;
+            sgps.t3cof = sgps.d2 + 2.0 * c1sq;
+            sgps.t4cof = 0.25 * (3.0 * sgps.d3 + sgps.c1 * (12.0 * sgps.d2 + 10.0 * c1sq));
+            sgps.t5cof = 0.2 * (3.0 * sgps.d4 + 12.0 * sgps.c1 * sgps.d3 + 6.0 * sgps.d2 * sgps.d2
+                                + 15.0 * c1sq * (2.0 * sgps.d2 + c1sq));
+        }
+
+        if simple {
+            sat.flags |= SIMPLE_FLAG;
+        }
+        else {
+            sat.flags &= !SIMPLE_FLAG;
+        }
+    }
+
+    let sgps = sat.sgps.clone();
+
+    // update for secular gravity and atmospheric drag
+    let xmdf = tle.xmo + sgps.xmdot * tsince;
+    let omgadf = tle.omegao + sgps.omgdot * tsince;
+    let xnoddf = tle.xnodeo + sgps.xnodot * tsince;
+    let mut omega = omgadf;
+    let mut xmp = xmdf;
+    let tsq = tsince * tsince;
+    let xnode = xnoddf + sgps.xnodcf * tsq;
+    let mut tempa = 1.0 - sgps.c1 * tsince;
+    let mut tempe = tle.bstar * sgps.c4 * tsince;
+    let mut templ = sgps.t2cof * tsq;
+
+    if sat.flags & SIMPLE_FLAG == 0 {
+        let delomg = sgps.omgcof * tsince;
+        let delm = sgps.xmcof * ((1.0 + sgps.eta * xmdf.cos()).powi(3) - sgps.delmo);
+        let temp = delomg + delm;
+        xmp = xmdf + temp;
+        omega = omgadf - temp;
+        let tcube = tsq * tsince;
+        let tfour = tsince * tcube;
+        tempa = tempa - sgps.d2 * tsq - sgps.d3 * tcube - sgps.d4 * tfour;
+        tempe += tle.bstar * sgps.c5 * (xmp.sin() - sgps.sinmo);
+        templ += sgps.t3cof * tcube + tfour * (sgps.t4cof + tsince * sgps.t5cof);
+    }
+
+    let a = sgps.aodp * tempa * tempa;
+    let e = tle.eo - tempe;
+    let xl = xmp + omega + xnode + sgps.xnodp * templ;
+    let beta = (1.0 - e * e).sqrt();
+    let xn = XKE / a.powf(1.5);
+
+    // long period periodics
+    let axn = e * omega.cos();
+    let temp = 1.0 / (a * beta * beta);
+    let xll = temp * sgps.xlcof * axn;
+    let aynl = temp * sgps.aycof;
+    let xlt = xl + xll;
+    let ayn = e * omega.sin() + aynl;
+
+    // solve Kepler's equation
+    let capu = (xlt - xnode).rem_euclid(TWOPI);
+    let mut temp2 = capu;
+    let (mut sinepw, mut cosepw, mut temp3, mut temp4, mut temp5, mut temp6);
+    let mut i = 0;
+    loop {
+        sinepw = temp2.sin();
+        cosepw = temp2.cos();
+        temp3 = axn * sinepw;
+        temp4 = ayn * cosepw;
+        temp5 = axn * cosepw;
+        temp6 = ayn * sinepw;
+        let epw = (capu - temp4 + temp3 - temp2) / (1.0 - temp5 - temp6) + temp2;
+        if (epw - temp2).abs() <= E6A || i >= 10 {
+            break;
+        }
+        temp2 = epw;
+        i += 1;
+    }
+
+    // short period preliminary quantities
+    let ecose = temp5 + temp6;
+    let esine = temp3 - temp4;
+    let elsq = axn * axn + ayn * ayn;
+    let temp = 1.0 - elsq;
+    let pl = a * temp;
+    let r = a * (1.0 - ecose);
+    let temp1 = 1.0 / r;
+    let rdot = XKE * a.sqrt() * esine * temp1;
+    let rfdot = XKE * pl.sqrt() * temp1;
+    let temp2 = a * temp1;
+    let betal = temp.sqrt();
+    let temp3 = 1.0 / (1.0 + betal);
+    let cosu = temp2 * (cosepw - axn + ayn * esine * temp3);
+    let sinu = temp2 * (sinepw - ayn - axn * esine * temp3);
+    let u = sinu.atan2(cosu);
+    let sin2u = 2.0 * sinu * cosu;
+    let cos2u = 2.0 * cosu * cosu - 1.0;
+    let temp = 1.0 / pl;
+    let temp1 = CK2 * temp;
+    let temp2 = temp1 * temp;
+
+    // update for short periodics
+    let rk = r * (1.0 - 1.5 * temp2 * betal * sgps.x3thm1) + 0.5 * temp1 * sgps.x1mth2 * cos2u;
+    let uk = u - 0.25 * temp2 * sgps.x7thm1 * sin2u;
+    let xnodek = xnode + 1.5 * temp2 * sgps.cosio * sin2u;
+    let xinck = tle.xincl + 1.5 * temp2 * sgps.cosio * sgps.sinio * cos2u;
+    let rdotk = rdot - xn * temp1 * sgps.x1mth2 * sin2u;
+    let rfdotk = rfdot + xn * temp1 * (sgps.x1mth2 * cos2u + 1.5 * sgps.x3thm1);
+
+    // orientation vectors
+    let (sinuk, cosuk) = uk.sin_cos();
+    let (sinik, cosik) = xinck.sin_cos();
+    let (sinnok, cosnok) = xnodek.sin_cos();
+    let xmx = -sinnok * cosik;
+    let xmy = cosnok * cosik;
+    let ux = xmx * sinuk + cosnok * cosuk;
+    let uy = xmy * sinuk + sinnok * cosuk;
+    let uz = sinik * sinuk;
+    let vx = xmx * cosuk - cosnok * sinuk;
+    let vy = xmy * cosuk - sinnok * sinuk;
+    let vz = sinik * cosuk;
+
+    // position and velocity
+    sat.pos.x = rk * ux;
+    sat.pos.y = rk * uy;
+    sat.pos.z = rk * uz;
+    sat.vel.x = rdotk * ux + rfdotk * vx;
+    sat.vel.y = rdotk * uy + rfdotk * vy;
+    sat.vel.z = rdotk * uz + rfdotk * vz;
+
+    // phase in radians
+    sat.phase = (xlt - xnode - omgadf + TWOPI).rem_euclid(TWOPI);
+
+    sat.tle.omegao1 = omega;
+    sat.tle.xincl1 = xinck;
+    sat.tle.xnodeo1 = xnodek;
+}
+
+#[test]
+fn sgp4_spacetrack_report_3() {
+    use {Location, Predict};
+    use ::clock::from_julian_date;
+
+    let tle = ::spacetrack_report_3();
+    let mut predict = Predict::new(&tle, Location::default()).unwrap();
+    let epoch = tle::julian_date_of_epoch(80275.98708465);
+
+    for &(tsince, pos, vel) in ::SPACETRACK_REPORT_3_EPHEMERIS.iter() {
+        let sat = predict.observe_at(from_julian_date(epoch + tsince / XMNPDA));
+        let (eci_pos, eci_vel) = (sat.eci_position_km(), sat.eci_velocity_km_s());
+        for i in 0..3 {
+            assert!((eci_pos[i] - pos[i]).abs() < 0.01, "tsince {}: {:?} != {:?}", tsince, eci_pos, pos);
+            assert!((eci_vel[i] - vel[i]).abs() < 1e-5, "tsince {}: {:?} != {:?}", tsince, eci_vel, vel);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::f64::consts::PI;
+use chrono::{DateTime, Utc};
+
+use ::clock::to_julian_date;
+use ::coords::{self, EARTH_RADIUS_KM};
+use ::location::{self, Location};
+
+/// astronomical unit [km]
+const AU_KM: f64 = 1.49597870691e8;
+
+/// solar radius [km]
+const SUN_RADIUS_KM: f64 = 6.96e5;
+
+/// Sun position in ECI coordinates [km] at the given time, computed the same
+/// way as in gpredict's solar.c.
+pub fn position(t: DateTime<Utc>) -> [f64; 3] {
+    position_jd(to_julian_date(t))
+}
+
+pub(crate) fn position_jd(jd: f64) -> [f64; 3] {
+    let mjd = jd - 2415020.0;
+    let year = 1900.0 + mjd / 365.25;
+    let t = (mjd + delta_et(year) / 86400.0) / 36525.0;
+
+    let m = modulus(358.47583 + modulus(35999.04975 * t, 360.0)
+                    - (0.000150 + 0.0000033 * t) * t * t, 360.0).to_radians();
+    let l = modulus(279.69668 + modulus(36000.76892 * t, 360.0) + 0.0003025 * t * t, 360.0).to_radians();
+    let e = 0.01675104 - (0.0000418 + 0.000000126 * t) * t;
+    let c = ((1.919460 - (0.004789 + 0.000014 * t) * t) * m.sin()
+             + (0.020094 - 0.000100 * t) * (2.0 * m).sin()
+             + 0.000293 * (3.0 * m).sin()).to_radians();
+    let o = modulus(259.18 - 1934.142 * t, 360.0).to_radians();
+    let lsa = modulus(l + c - (0.00569 - 0.00479 * o.sin()).to_radians(), 2.0 * PI);
+    let nu = modulus(m + c, 2.0 * PI);
+    let r = AU_KM * 1.0000002 * (1.0 - e * e) / (1.0 + e * nu.cos());
+    let eps = (23.452294 - (0.0130125 + (0.00000164 - 0.000000503 * t) * t) * t
+               + 0.00256 * o.cos()).to_radians();
+
+    [r * lsa.cos(), r * lsa.sin() * eps.cos(), r * lsa.sin() * eps.sin()]
+}
+
+/// Topocentric `(az_deg, el_deg)` of the sun seen from `observer`, e.g. as a
+/// known target for calibrating a rotator.
+pub fn look_angles(observer: &Location, t: DateTime<Utc>) -> (f64, f64) {
+    let (az, el, _) = location::look_angles(observer, position(t), t);
+    (az, el)
+}
+
+/// Sub-solar point `(lat_deg, lon_deg)` at the given time, the point on the
+/// earth where the sun is at zenith.
+pub fn subsolar_point(t: DateTime<Utc>) -> (f64, f64) {
+    let jd = to_julian_date(t);
+    let sun = position_jd(jd);
+
+    let lat = (sun[2] / coords::magnitude(&sun)).asin();
+    let lon = sun[1].atan2(sun[0]) - coords::gmst_jd(jd);
+    let lon = modulus(lon + PI, 2.0 * PI) - PI;
+
+    (lat.to_degrees(), lon.to_degrees())
+}
+
+/// `points` points `(lat_deg, lon_deg)` on the day/night terminator at the
+/// given time, i.e. the circle 90° away from the sub-solar point.
+pub fn terminator(t: DateTime<Utc>, points: usize) -> Vec<(f64, f64)> {
+    let (lat, lon) = subsolar_point(t);
+    coords::small_circle(lat, lon, PI / 2.0, points)
+}
+
+/// Returns true if a satellite at ECI position `sat_km` is in the earth's
+/// umbra, given the sun ECI position `sun_km`.
+pub fn is_eclipsed(sat_km: &[f64; 3], sun_km: &[f64; 3]) -> bool {
+    let rho = [sun_km[0] - sat_km[0], sun_km[1] - sat_km[1], sun_km[2] - sat_km[2]];
+    let earth = [-sat_km[0], -sat_km[1], -sat_km[2]];
+
+    // semi-diameters of earth and sun as seen from the satellite
+    let sd_earth = (EARTH_RADIUS_KM / coords::magnitude(sat_km)).asin();
+    let sd_sun = (SUN_RADIUS_KM / coords::magnitude(&rho)).asin();
+    let delta = coords::angle(sun_km, &earth);
+
+    sd_earth >= sd_sun && sd_earth - sd_sun - delta >= 0.0
+}
+
+// difference between ephemeris time and universal time [s]
+fn delta_et(year: f64) -> f64 {
+    26.465 + 0.747622 * (year - 1950.0) + 1.886913 * (2.0 * PI * (year - 1975.0) / 33.0).sin()
+}
+
+fn modulus(a: f64, b: f64) -> f64 {
+    let m = a % b;
+    if m < 0.0 { m + b } else { m }
+}
+
+#[test]
+fn sun_eclipse() {
+    let sun = position_jd(2457300.5);
+    let behind = [-sun[0] / AU_KM * 7000.0, -sun[1] / AU_KM * 7000.0, -sun[2] / AU_KM * 7000.0];
+    let in_front = [-behind[0], -behind[1], -behind[2]];
+
+    assert!((coords::magnitude(&sun) / AU_KM - 1.0).abs() < 0.02);
+    assert!(is_eclipsed(&behind, &sun));
+    assert!(!is_eclipsed(&in_front, &sun));
+}
+
+#[test]
+fn sun_subsolar_point() {
+    use ::clock::from_julian_date;
+
+    // close to the march equinox the sun is above the equator
+    let (lat, _) = subsolar_point(from_julian_date(2457467.5));
+    assert!(lat.abs() < 1.0);
+
+    // and at the june solstice above the tropic of cancer
+    let (lat, _) = subsolar_point(from_julian_date(2457560.5));
+    assert!((lat - 23.44).abs() < 0.5);
+
+    for (lat, lon) in terminator(from_julian_date(2457560.5), 36) {
+        assert!(lat.abs() <= 90.0 && lon.abs() <= 180.0);
+    }
+}
@@ -22,9 +22,18 @@
  * SOFTWARE.
  */
 
+use clock::from_julian_date;
 use ffipredict;
+use predict::PredictError;
+use coords::{EARTH_GM_KM3_S2, EARTH_RADIUS_KM};
 
+use std::collections::HashMap;
+use std::error::Error;
+use std::f64::consts::PI;
 use std::ffi::CString;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use chrono::{DateTime, Utc};
 use libc::c_char;
 use std::{cmp, ptr};
 use std::mem::transmute;
@@ -32,15 +41,340 @@ use std::path::Path;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// length of a TLE line including the checksum digit
+const LINE_LENGTH: usize = 69;
+
+/// perigee altitude below which an object has most likely reentered [km]
+const DECAY_PERIGEE_KM: f64 = 120.0;
+
+/// first derivative of the mean motion / 2 above which an object decays
+/// within days [rev/day^2]
+const DECAY_MEAN_MOTION_DOT: f64 = 0.01;
 
 #[derive(Debug, Clone, PartialEq)]
+pub enum TleError {
+    /// line is not 69 characters long
+    BadLineLength { line: u8, got: usize },
+
+    /// line does not start with its line number
+    BadLineNumber { line: u8 },
+
+    /// line 1 and line 2 describe different satellites
+    CatalogNumberMismatch,
+
+    /// mod-10 checksum digit does not match the line contents
+    BadChecksum { line: u8, expected: u8, got: u8 },
+
+    /// element set ends before the given line
+    MissingLine { line: u8 },
+
+    /// OMM input is not a JSON object
+    InvalidJson,
+
+    /// OMM field is missing or has an invalid value
+    BadOmmField { field: &'static str },
+}
+
+impl fmt::Display for TleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TleError::BadLineLength { line, got } =>
+                write!(f, "line {} has {} characters, expected {}", line, got, LINE_LENGTH),
+            TleError::BadLineNumber { line } =>
+                write!(f, "line {} does not start with '{}'", line, line),
+            TleError::CatalogNumberMismatch =>
+                write!(f, "catalog numbers of line 1 and line 2 differ"),
+            TleError::BadChecksum { line, expected, got } =>
+                write!(f, "line {} has checksum {}, expected {}", line, got, expected),
+            TleError::MissingLine { line } =>
+                write!(f, "line {} is missing", line),
+            TleError::InvalidJson =>
+                write!(f, "OMM is not a JSON object"),
+            TleError::BadOmmField { field } =>
+                write!(f, "OMM field {} is missing or invalid", field),
+        }
+    }
+}
+
+impl Error for TleError {}
+
+/// A `Tle` converted to the representation used by the propagator, for
+/// creating many `Predict`s without parsing the elements again.
+#[derive(Debug, Clone)]
+pub struct ParsedTle {
+    pub(crate) tle_t: ffipredict::tle_t,
+    pub(crate) name: CString,
+}
+
+impl ParsedTle {
+    pub fn new(tle: &Tle) -> Result<ParsedTle, PredictError> {
+        let name = CString::new(tle.name.as_str()).map_err(|_| PredictError::TleParse("name"))?;
+        Ok(ParsedTle { tle_t: create_tle_t(tle)?, name: name })
+    }
+}
+
+/// Mean Keplerian elements of a `Tle`, see `Tle::elements`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrbitalElements {
+    /// inclination [deg]
+    pub inclination_deg:        f64,
+
+    /// right ascension of the ascending node [deg]
+    pub raan_deg:               f64,
+
+    pub eccentricity:           f64,
+
+    /// argument of perigee [deg]
+    pub arg_of_perigee_deg:     f64,
+
+    /// mean anomaly [deg]
+    pub mean_anomaly_deg:       f64,
+
+    /// mean motion [rev/day]
+    pub mean_motion_rev_day:    f64,
+
+    /// B* drag term [1/earth radii]
+    pub bstar:                  f64,
+}
+
+/// Error downloading element sets, see `fetch_celestrak`
+#[cfg(feature = "fetch")]
+#[derive(Debug)]
+pub enum FetchError {
+    /// request failed or the server answered with an error status
+    Http(reqwest::Error),
+
+    /// the element set of a single satellite query is invalid
+    Tle(TleError),
+
+    /// the response contains no element sets
+    NotFound,
+}
+
+#[cfg(feature = "fetch")]
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FetchError::Http(ref e) => write!(f, "TLE download failed: {}", e),
+            FetchError::Tle(ref e) => write!(f, "invalid TLE in download: {}", e),
+            FetchError::NotFound => write!(f, "no TLE found"),
+        }
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl Error for FetchError {}
+
+/// Two line element set.
+///
+/// Element sets are equal if they have the same catalog number and epoch,
+/// regardless of name or formatting. Without a valid catalog number both
+/// lines have to match.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Tle {
     pub name: String,
     pub line1: String,
     pub line2: String,
 }
 
+impl PartialEq for Tle {
+    fn eq(&self, other: &Tle) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Tle {}
+
+impl Hash for Tle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
 impl Tle {
+    /// Creates a `Tle` after validating line length, line numbers, catalog
+    /// numbers and checksums of both lines.
+    pub fn parse(name: &str, line1: &str, line2: &str) -> Result<Tle, TleError> {
+        let tle = Tle {
+            name: trim(&name.to_string()),
+            line1: trim(&line1.to_string()),
+            line2: trim(&line2.to_string()),
+        };
+
+        check_line(&tle.line1, 1)?;
+        check_line(&tle.line2, 2)?;
+
+        if tle.line1.as_bytes()[2..7] != tle.line2.as_bytes()[2..7] {
+            return Err(TleError::CatalogNumberMismatch);
+        }
+
+        Ok(tle)
+    }
+
+    /// Creates a `Tle` from a JSON OMM (orbit mean-elements message) object as
+    /// distributed by Celestrak with `FORMAT=json`.
+    ///
+    /// Line 1 and line 2 are rebuilt from the mean elements with fresh
+    /// checksums. Fields which the two line format can't hold exactly are
+    /// rounded to its column widths.
+    #[cfg(feature = "serde")]
+    pub fn from_omm_json(s: &str) -> Result<Tle, TleError> {
+        let omm: serde_json::Value = serde_json::from_str(s).map_err(|_| TleError::InvalidJson)?;
+        if !omm.is_object() {
+            return Err(TleError::InvalidJson);
+        }
+
+        let number = |field: &'static str| omm.get(field).and_then(|v| v.as_f64())
+                                              .ok_or(TleError::BadOmmField { field: field });
+        let integer = |field: &'static str| omm.get(field).and_then(|v| v.as_u64())
+                                               .ok_or(TleError::BadOmmField { field: field });
+        let text = |field: &'static str| omm.get(field).and_then(|v| v.as_str()).unwrap_or("");
+        // values which don't fit their fixed width TLE columns
+        let bounded = |field: &'static str, min: f64, max: f64| {
+            number(field).and_then(|v| if v >= min && v < max { Ok(v) } else { Err(TleError::BadOmmField { field: field }) })
+        };
+        let formatted = |field: &'static str, format: fn(f64) -> Option<String>| {
+            number(field).and_then(|v| format(v).ok_or(TleError::BadOmmField { field: field }))
+        };
+
+        let catnr = integer("NORAD_CAT_ID")?;
+        if catnr > 99999 {
+            return Err(TleError::BadOmmField { field: "NORAD_CAT_ID" });
+        }
+        let epoch = omm_epoch(text("EPOCH")).ok_or(TleError::BadOmmField { field: "EPOCH" })?;
+        let classification = text("CLASSIFICATION_TYPE").chars().next().unwrap_or('U');
+        let eccentricity = number("ECCENTRICITY")?;
+        if eccentricity < 0.0 || eccentricity >= 1.0 {
+            return Err(TleError::BadOmmField { field: "ECCENTRICITY" });
+        }
+
+        let intl_designator = omm_intl_designator(text("OBJECT_ID"))
+                                  .ok_or(TleError::BadOmmField { field: "OBJECT_ID" })?;
+
+        let line1 = format!("1 {:05}{} {:8} {} {} {} {} {} {:4}",
+                            catnr, classification, intl_designator, epoch,
+                            formatted("MEAN_MOTION_DOT", omm_decimal_field)?,
+                            formatted("MEAN_MOTION_DDOT", omm_exp_field)?,
+                            formatted("BSTAR", omm_exp_field)?,
+                            integer("EPHEMERIS_TYPE").unwrap_or(0) % 10,
+                            integer("ELEMENT_SET_NO")? % 10000);
+        let line2 = format!("2 {:05} {:8.4} {:8.4} {:07.0} {:8.4} {:8.4} {:11.8}{:5}",
+                            catnr, bounded("INCLINATION", 0.0, 180.00005)?, bounded("RA_OF_ASC_NODE", 0.0, 360.0)?,
+                            eccentricity * 1e7, bounded("ARG_OF_PERICENTER", 0.0, 360.0)?,
+                            bounded("MEAN_ANOMALY", 0.0, 360.0)?, bounded("MEAN_MOTION", 0.0, 100.0)?,
+                            integer("REV_AT_EPOCH")? % 100000);
+
+        let line1 = format!("{}{}", line1, checksum(line1.as_bytes()));
+        let line2 = format!("{}{}", line2, checksum(line2.as_bytes()));
+
+        Tle::parse(text("OBJECT_NAME"), &line1, &line2)
+    }
+
+    /// Epoch of the element set from line 1, `None` if the field is malformed.
+    pub fn epoch(&self) -> Option<DateTime<Utc>> {
+        let field = self.line1.get(18..32)?.trim();
+        let epoch: f64 = field.parse().ok()?;
+
+        Some(from_julian_date(julian_date_of_epoch(epoch)))
+    }
+
+    /// Returns true if the epoch of this element set is later than the one of
+    /// `other`, false if either epoch is malformed.
+    pub fn is_newer_than(&self, other: &Tle) -> bool {
+        match (self.epoch(), other.epoch()) {
+            (Some(a), Some(b)) => a > b,
+            _ => false,
+        }
+    }
+
+    /// Apogee altitude [km] above the equatorial radius, from mean motion
+    /// and eccentricity.
+    pub fn apogee_km(&self) -> Option<f64> {
+        let (a, e) = self.semi_major_axis_and_eccentricity()?;
+        Some(a * (1.0 + e) - EARTH_RADIUS_KM)
+    }
+
+    /// Perigee altitude [km] above the equatorial radius, from mean motion
+    /// and eccentricity.
+    pub fn perigee_km(&self) -> Option<f64> {
+        let (a, e) = self.semi_major_axis_and_eccentricity()?;
+        Some(a * (1.0 - e) - EARTH_RADIUS_KM)
+    }
+
+    // semi major axis [km] from the mean motion [rev/day] and the eccentricity
+    fn semi_major_axis_and_eccentricity(&self) -> Option<(f64, f64)> {
+        let mean_motion: f64 = number_field(&self.line2, 52..63)?;
+        let eccentricity: f64 = format!("0.{}", self.line2.get(26..33)?.trim()).parse().ok()?;
+
+        Some((semi_major_axis_km(mean_motion), eccentricity))
+    }
+
+    /// Returns true if the elements most likely describe an object which has
+    /// reentered or is about to, i.e. the perigee is below 120 km or the
+    /// mean motion increases fast due to drag. SGP4 gives nonsense for these.
+    ///
+    /// False if the fields can't be parsed.
+    pub fn is_likely_decayed(&self) -> bool {
+        let mean_motion_dot: f64 = match number_field(&self.line1, 33..43) {
+            Some(ndot) => ndot,
+            None => return false,
+        };
+
+        match self.semi_major_axis_and_eccentricity() {
+            Some((a, e)) => is_decaying(a, e, mean_motion_dot),
+            None => false,
+        }
+    }
+
+    // catalog number and the unparsed epoch field, the full lines if the
+    // catalog number is malformed
+    fn key(&self) -> (Option<u32>, &str, &str) {
+        match self.catalog_number() {
+            Some(catnr) => (Some(catnr), self.line1.get(18..32).unwrap_or("").trim(), ""),
+            None => (None, &self.line1, &self.line2),
+        }
+    }
+
+    /// NORAD catalog number from line 1.
+    pub fn catalog_number(&self) -> Option<u32> {
+        number_field(&self.line1, 2..7)
+    }
+
+    /// International designator from line 1, e.g. "15003D", empty if unknown.
+    pub fn intl_designator(&self) -> String {
+        self.line1.get(9..17).unwrap_or("").trim().to_string()
+    }
+
+    /// Element set number from line 1.
+    pub fn element_set_number(&self) -> Option<u16> {
+        number_field(&self.line1, 64..68)
+    }
+
+    /// Mean elements as parsed for the propagator.
+    pub fn elements(&self) -> Result<OrbitalElements, PredictError> {
+        let tle_t = create_tle_t(self)?;
+
+        Ok(OrbitalElements {
+            inclination_deg:        tle_t.xincl,
+            raan_deg:               tle_t.xnodeo,
+            eccentricity:           tle_t.eo,
+            arg_of_perigee_deg:     tle_t.omegao,
+            mean_anomaly_deg:       tle_t.xmo,
+            mean_motion_rev_day:    tle_t.xno,
+            bstar:                  tle_t.bstar,
+        })
+    }
+
+    /// Revolution number at epoch from line 2.
+    pub fn revolution_number(&self) -> Option<u32> {
+        number_field(&self.line2, 63..68)
+    }
+
     pub fn from_file(tlename: &str, pathstr: &str) -> Result<Tle, String> {
         let path = Path::new(&pathstr);
         let file = File::open(&path);
@@ -79,6 +413,203 @@ impl Tle {
     }
 }
 
+fn semi_major_axis_km(mean_motion_rev_day: f64) -> f64 {
+    let n = mean_motion_rev_day * 2.0 * PI / 86400.0;
+    (EARTH_GM_KM3_S2 / (n * n)).cbrt()
+}
+
+// see Tle::is_likely_decayed
+fn is_decaying(semi_major_axis_km: f64, eccentricity: f64, mean_motion_dot: f64) -> bool {
+    semi_major_axis_km * (1.0 - eccentricity) - EARTH_RADIUS_KM < DECAY_PERIGEE_KM
+        || mean_motion_dot > DECAY_MEAN_MOTION_DOT
+}
+
+// Tle::is_likely_decayed for the raw elements of a tle_t
+pub(crate) fn is_likely_decayed(tle: &ffipredict::tle_t) -> bool {
+    is_decaying(semi_major_axis_km(tle.xno), tle.eo, tle.xndt2o)
+}
+
+fn number_field<T: FromStr>(line: &str, columns: Range<usize>) -> Option<T> {
+    line.get(columns)?.trim().parse().ok()
+}
+
+pub(crate) fn julian_date_of_epoch(epoch: f64) -> f64 {
+    // valid 1957 through 2056
+    let year = (epoch * 1e-3).trunc();
+    let day = epoch - year * 1e3;
+    let year = if year < 57.0 { year + 2000.0 } else { year + 1900.0 };
+
+    julian_date_of_year(year) + day
+}
+
+// Julian date of 0.0 Jan year, Meeus, Astronomical Formulae for Calculators
+fn julian_date_of_year(year: f64) -> f64 {
+    let year = year - 1.0;
+    let a = (year / 100.0).trunc();
+    let b = 2.0 - a + (a / 4.0).trunc();
+    let i = (365.25 * year).trunc() + (30.6001f64 * 14.0).trunc();
+
+    i + 1720994.5 + b
+}
+
+/// Parses a text containing many element sets, e.g. a Celestrak bulk file.
+///
+/// Element sets consist of an optional name line followed by line 1 and line 2.
+/// Blank lines and trailing whitespace are ignored. Every element set gets its
+/// own result so one bad satellite does not discard the rest.
+pub fn parse_multiple(input: &str) -> Vec<Result<Tle, TleError>> {
+    let lines: Vec<&str> = input.lines()
+                                .map(|l| l.trim_end())
+                                .filter(|l| !l.is_empty())
+                                .collect();
+    let mut tles = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let name = if lines[i].starts_with("1 ") {
+            ""
+        }
+        else {
+            i += 1;
+            lines[i - 1]
+        };
+
+        if i >= lines.len() {
+            tles.push(Err(TleError::MissingLine { line: 1 }));
+        }
+        else if i + 1 >= lines.len() {
+            tles.push(Err(TleError::MissingLine { line: 2 }));
+        }
+        else {
+            tles.push(Tle::parse(name, lines[i], lines[i + 1]));
+        }
+
+        i += 2;
+    }
+
+    tles
+}
+
+// "2015-08-31T10:14:54.768192" to the TLE epoch field "15243.42702278"
+#[cfg(feature = "serde")]
+fn omm_epoch(epoch: &str) -> Option<String> {
+    let mut parts = epoch.trim_end_matches('Z').split('T');
+    let date: Vec<u32> = parts.next()?.split('-').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let time: Vec<f64> = parts.next().unwrap_or("0:0:0").split(':').map(|n| n.parse().ok())
+                                                                   .collect::<Option<_>>()?;
+    if date.len() != 3 || time.len() != 3 || date[1] < 1 || date[1] > 12 {
+        return None;
+    }
+
+    let (year, month, day) = (date[0], date[1], date[2]);
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = [31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    // two digit TLE years cover 1957 through 2056
+    if year < 1957 || year > 2056 || day < 1 || day > days_in_month[month as usize - 1] {
+        return None;
+    }
+    if !(time[0] >= 0.0 && time[0] < 24.0 && time[1] >= 0.0 && time[1] < 60.0 && time[2] >= 0.0 && time[2] < 60.0) {
+        return None;
+    }
+
+    let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let mut day_of_year = days_before_month[month as usize - 1] + day;
+    if leap && month > 2 {
+        day_of_year += 1;
+    }
+    let fraction = (time[0] * 3600.0 + time[1] * 60.0 + time[2]) / 86400.0;
+
+    Some(format!("{:02}{:012.8}", year % 100, day_of_year as f64 + fraction))
+}
+
+// "2015-003D" to "15003D", empty if unknown, `None` if it is not of the form
+// "YYYY-NNNP" with up to three piece letters
+#[cfg(feature = "serde")]
+fn omm_intl_designator(object_id: &str) -> Option<String> {
+    if object_id.is_empty() {
+        return Some(String::new());
+    }
+
+    let bytes = object_id.as_bytes();
+    let valid = object_id.is_ascii() && bytes.len() >= 8 && bytes.len() <= 11 && bytes[4] == b'-'
+                && bytes[..4].iter().chain(&bytes[5..8]).all(|b| b.is_ascii_digit())
+                && bytes[8..].iter().all(|b| b.is_ascii_alphabetic());
+    if !valid {
+        return None;
+    }
+
+    Some(format!("{}{}", &object_id[2..4], &object_id[5..]))
+}
+
+// first derivative of the mean motion as " .00003367", `None` if the value
+// does not fit the field
+#[cfg(feature = "serde")]
+fn omm_decimal_field(value: f64) -> Option<String> {
+    let digits = format!("{:.8}", value.abs());
+    if !digits.starts_with("0.") {
+        return None;
+    }
+
+    Some(format!("{}{}", if value < 0.0 { '-' } else { ' ' }, &digits[1..]))
+}
+
+// value with an implied leading decimal point and exponent as " 17130-3",
+// `None` if the exponent has more than one digit
+#[cfg(feature = "serde")]
+fn omm_exp_field(value: f64) -> Option<String> {
+    let sign = if value < 0.0 { '-' } else { ' ' };
+    if value == 0.0 {
+        return Some(format!("{}00000-0", sign));
+    }
+    if !value.is_finite() {
+        return None;
+    }
+
+    let mut exponent = value.abs().log10().floor() as i32 + 1;
+    let mut mantissa = (value.abs() / 10f64.powi(exponent) * 1e5).round();
+    if mantissa >= 1e5 {
+        mantissa /= 10.0;
+        exponent += 1;
+    }
+
+    if exponent.abs() > 9 {
+        return None;
+    }
+
+    Some(format!("{}{:05}{}{}", sign, mantissa as u32, if exponent < 0 { '-' } else { '+' }, exponent.abs()))
+}
+
+fn check_line(line: &str, nr: u8) -> Result<(), TleError> {
+    let bytes = line.as_bytes();
+
+    if bytes.len() != LINE_LENGTH {
+        return Err(TleError::BadLineLength { line: nr, got: bytes.len() });
+    }
+
+    if bytes[0] != b'0' + nr || bytes[1] != b' ' {
+        return Err(TleError::BadLineNumber { line: nr });
+    }
+
+    let expected = checksum(&bytes[..LINE_LENGTH - 1]);
+    let got = bytes[LINE_LENGTH - 1].wrapping_sub(b'0');
+    if got != expected {
+        return Err(TleError::BadChecksum { line: nr, expected: expected, got: got });
+    }
+
+    Ok(())
+}
+
+// mod-10 checksum, digits count their value and '-' counts as 1
+fn checksum(bytes: &[u8]) -> u8 {
+    let sum = bytes.iter().fold(0u32, |sum, &b| match b {
+        b'0'..=b'9' => sum + (b - b'0') as u32,
+        b'-' => sum + 1,
+        _ => sum,
+    });
+
+    (sum % 10) as u8
+}
+
 fn copy_memory(src: &[u8], dst: &mut [u8]) -> usize {
     let len = cmp::min(src.len(), dst.len());
     unsafe {
@@ -95,7 +626,7 @@ fn trim(line: &String) -> String {
     l
 }
 
-pub fn create_tle_t(tle: &Tle) -> Result<ffipredict::tle_t, &'static str> {
+pub(crate) fn create_tle_t(tle: &Tle) -> Result<ffipredict::tle_t, PredictError> {
     let mut tle_t = ffipredict::tle_t {
         epoch: 0.0,
         epoch_year: 0,
@@ -125,9 +656,15 @@ pub fn create_tle_t(tle: &Tle) -> Result<ffipredict::tle_t, &'static str> {
         //..Default::default()
     };
 
-    let name = CString::new(tle.name.clone()).unwrap();
-    let line1 = CString::new(tle.line1.clone()).unwrap();
-    let line2 = CString::new(tle.line2.clone()).unwrap();
+    // libgpredict reads fixed columns, so broken lines are rejected up front
+    let line1 = trim(&tle.line1);
+    let line2 = trim(&tle.line2);
+    check_line(&line1, 1).map_err(PredictError::InvalidTle)?;
+    check_line(&line2, 2).map_err(PredictError::InvalidTle)?;
+
+    let name = to_c_field(&tle.name, "name")?;
+    let line1 = to_c_field(&line1, "line1")?;
+    let line2 = to_c_field(&line2, "line2")?;
     let mut buf = [[0u8; 80]; 3];
 
     copy_memory(name.as_bytes_with_nul(), &mut buf[0]);
@@ -141,6 +678,231 @@ pub fn create_tle_t(tle: &Tle) -> Result<ffipredict::tle_t, &'static str> {
         Ok(tle_t)
     }
     else {
-        Err("error in TLE parsing")
+        Err(PredictError::TleParse("elements"))
+    }
+}
+
+// converts a TLE field to a C string which fits into the 80 byte line buffer
+fn to_c_field(value: &str, field: &'static str) -> Result<CString, PredictError> {
+    match CString::new(value) {
+        Ok(ref s) if s.as_bytes_with_nul().len() > 80 => Err(PredictError::TleParse(field)),
+        Ok(s) => Ok(s),
+        Err(_) => Err(PredictError::TleParse(field)),
+    }
+}
+
+/// Celestrak GP query URL
+#[cfg(feature = "fetch")]
+const CELESTRAK_URL: &str = "https://celestrak.org/NORAD/elements/gp.php";
+
+/// Downloads all element sets of a Celestrak group, e.g. "amateur" or "stations".
+///
+/// Every element set gets its own result, like `parse_multiple`, so one bad
+/// entry does not discard the rest of the group.
+#[cfg(feature = "fetch")]
+pub fn fetch_celestrak(group: &str) -> Result<Vec<Result<Tle, TleError>>, FetchError> {
+    fetch(&format!("{}?GROUP={}&FORMAT=tle", CELESTRAK_URL, percent_encode(group)))
+}
+
+/// Downloads the current element set of a satellite by NORAD catalog number.
+#[cfg(feature = "fetch")]
+pub fn fetch_by_catnr(norad_id: u32) -> Result<Tle, FetchError> {
+    fetch(&format!("{}?CATNR={}&FORMAT=tle", CELESTRAK_URL, norad_id))?
+        .into_iter()
+        .next()
+        .ok_or(FetchError::NotFound)?
+        .map_err(FetchError::Tle)
+}
+
+#[cfg(feature = "fetch")]
+fn fetch(url: &str) -> Result<Vec<Result<Tle, TleError>>, FetchError> {
+    let text = reqwest::blocking::get(url)
+                   .and_then(|response| response.error_for_status())
+                   .and_then(|response| response.text())
+                   .map_err(FetchError::Http)?;
+
+    // Celestrak answers unknown queries with a plain text message
+    if !text.lines().any(|l| l.starts_with("1 ")) {
+        return Err(FetchError::NotFound);
     }
+
+    Ok(parse_multiple(&text))
+}
+
+// percent-encodes everything but the RFC 3986 unreserved characters, so a
+// query value can't add parameters of its own
+#[cfg(any(feature = "fetch", test))]
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// Keeps only the newest element set of every catalog number, e.g. after
+/// combining several Celestrak groups.
+///
+/// The order of first appearance is kept, element sets without a valid
+/// catalog number are passed through.
+pub fn dedup_latest(tles: Vec<Tle>) -> Vec<Tle> {
+    let mut latest: Vec<Tle> = Vec::new();
+    let mut index: HashMap<u32, usize> = HashMap::new();
+
+    for tle in tles {
+        match tle.catalog_number() {
+            Some(catnr) => {
+                match index.get(&catnr) {
+                    Some(&i) => {
+                        if tle.is_newer_than(&latest[i]) {
+                            latest[i] = tle;
+                        }
+                    }
+                    None => {
+                        index.insert(catnr, latest.len());
+                        latest.push(tle);
+                    }
+                }
+            }
+            None => latest.push(tle),
+        }
+    }
+
+    latest
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tle_from_omm_json() {
+    let omm = r#"{"OBJECT_NAME":"GRIFEX","OBJECT_ID":"2015-003D","EPOCH":"2015-08-31T10:14:54.768192",
+                  "MEAN_MOTION":15.07659299,"ECCENTRICITY":0.0157088,"INCLINATION":99.1124,
+                  "RA_OF_ASC_NODE":290.6779,"ARG_OF_PERICENTER":8.9691,"MEAN_ANOMALY":351.428,
+                  "EPHEMERIS_TYPE":0,"CLASSIFICATION_TYPE":"U","NORAD_CAT_ID":40379,"ELEMENT_SET_NO":999,
+                  "REV_AT_EPOCH":3188,"BSTAR":0.0001713,"MEAN_MOTION_DOT":3.367e-5,"MEAN_MOTION_DDOT":0}"#;
+
+    let tle = Tle::from_omm_json(omm).unwrap();
+    assert_eq!(tle.name, "GRIFEX");
+    assert_eq!(tle.line1, "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993");
+    assert_eq!(tle.line2, "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889");
+
+    assert_eq!(Tle::from_omm_json("[]"), Err(TleError::InvalidJson));
+    assert_eq!(Tle::from_omm_json(r#"{"OBJECT_NAME":"GRIFEX"}"#),
+               Err(TleError::BadOmmField { field: "NORAD_CAT_ID" }));
+
+    // values which do not fit their TLE columns
+    let bad = |from: &str, to: &str| Tle::from_omm_json(&omm.replace(from, to));
+    assert_eq!(bad("2015-003D", "aéa-1"), Err(TleError::BadOmmField { field: "OBJECT_ID" }));
+    assert_eq!(bad("3.367e-5", "1.5"), Err(TleError::BadOmmField { field: "MEAN_MOTION_DOT" }));
+    assert_eq!(bad("0.0001713", "1.7e-12"), Err(TleError::BadOmmField { field: "BSTAR" }));
+    assert_eq!(bad("2015-08-31T", "2015-02-30T"), Err(TleError::BadOmmField { field: "EPOCH" }));
+    assert_eq!(bad("99.1124", "1099.1124"), Err(TleError::BadOmmField { field: "INCLINATION" }));
+    assert!(bad("2015-003D", "").unwrap().line1.contains("U          15243"));
+}
+
+#[test]
+fn tle_parse() {
+    use ::clock::to_julian_date;
+
+    let line1 = "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993";
+    let line2 = "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889";
+
+    let tle = Tle::parse("GRIFEX", line1, line2).unwrap();
+    assert!((to_julian_date(tle.epoch().unwrap()) - 2457265.92702278).abs() < 1e-6);
+    assert_eq!(tle.catalog_number(), Some(40379));
+    assert_eq!(tle.intl_designator(), "15003D");
+    assert_eq!(tle.element_set_number(), Some(999));
+    assert_eq!(tle.revolution_number(), Some(3188));
+    assert!((tle.apogee_km().unwrap() - 652.1).abs() < 0.1);
+    assert!((tle.perigee_km().unwrap() - 434.6).abs() < 0.1);
+    let e = tle.elements().unwrap();
+    let expected = [99.1124, 290.6779, 0.0157088, 8.9691, 351.4280, 15.07659299, 0.17130e-3];
+    let got = [e.inclination_deg, e.raan_deg, e.eccentricity, e.arg_of_perigee_deg,
+               e.mean_anomaly_deg, e.mean_motion_rev_day, e.bstar];
+    for (g, x) in got.iter().zip(expected.iter()) {
+        assert!((g - x).abs() < 1e-12 * x.abs().max(1.0));
+    }
+    assert_eq!(Tle::parse("GRIFEX", &line1[..60], line2),
+               Err(TleError::BadLineLength { line: 1, got: 60 }));
+    assert_eq!(Tle::parse("GRIFEX", line2, line1), Err(TleError::BadLineNumber { line: 1 }));
+    assert_eq!(Tle::parse("GRIFEX", line1, &line2.replace("31889", "31888")),
+               Err(TleError::BadChecksum { line: 2, expected: 9, got: 8 }));
+    assert_eq!(Tle::parse("GRIFEX", line1, &line2.replace("40379", "40389").replace("31889", "31880")),
+               Err(TleError::CatalogNumberMismatch));
+}
+
+#[test]
+fn tle_likely_decayed() {
+    let (mut tle, _) = ::grifex();
+    assert!(!tle.is_likely_decayed());
+
+    // perigee ~70 km
+    tle.line2 = tle.line2.replace("15.07659299", "16.40000000");
+    assert!(tle.is_likely_decayed());
+
+    tle.line2 = tle.line2.replace("16.40000000", "15.07659299");
+    tle.line1 = tle.line1.replace(" .00003367", " .02503367");
+    assert!(tle.is_likely_decayed());
+}
+
+#[test]
+fn tle_parse_multiple() {
+    let input = "GRIFEX  \r
+1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993\r
+2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889\r
+
+BROKEN
+1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993
+2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31888
+1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993
+2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889
+";
+    let tles = parse_multiple(input);
+
+    assert_eq!(tles.len(), 3);
+    assert_eq!(tles[0].as_ref().unwrap().name, "GRIFEX");
+    assert!(tles[1].is_err());
+    assert_eq!(tles[2].as_ref().unwrap().name, "");
+}
+
+#[test]
+fn tle_dedup_latest() {
+    let (old, _) = ::grifex();
+    let new = Tle { line1: old.line1.replace("15243.42702278", "15244.42702278"), ..old.clone() };
+    let renamed = Tle { name: "GRIFEX 2".to_string(), ..old.clone() };
+
+    assert!(new.is_newer_than(&old));
+    assert!(!old.is_newer_than(&new));
+    assert_eq!(old, renamed);
+    assert!(old != new);
+
+    let tles = dedup_latest(vec![old.clone(), new.clone(), renamed]);
+    assert_eq!(tles.len(), 1);
+    assert_eq!(tles[0].line1, new.line1);
+}
+
+#[test]
+fn tle_percent_encode() {
+    assert_eq!(percent_encode("amateur"), "amateur");
+    assert_eq!(percent_encode("gps-ops"), "gps-ops");
+    assert_eq!(percent_encode("a&FORMAT=json"), "a%26FORMAT%3Djson");
+    assert_eq!(percent_encode("x y/ä"), "x%20y%2F%C3%A4");
+}
+
+#[test]
+fn tle_equality() {
+    use std::collections::HashSet;
+
+    let tle = Tle::parse("GRIFEX",
+                         "1 40379U 15003D   15243.42702278  .00003367  00000-0  17130-3 0  9993",
+                         "2 40379  99.1124 290.6779 0157088   8.9691 351.4280 15.07659299 31889").unwrap();
+    let renamed = Tle { name: "EXACTVIEW-1".to_string(), ..tle.clone() };
+    assert_eq!(tle, renamed);
+
+    // unrelated element sets with malformed catalog numbers are kept apart
+    let broken_a = Tle { name: "".to_string(), line1: "1 4O379U".to_string(), line2: "2 a".to_string() };
+    let broken_b = Tle { name: "".to_string(), line1: "1 4O379U".to_string(), line2: "2 b".to_string() };
+    assert_ne!(broken_a, broken_b);
+    assert_eq!(broken_a, broken_a.clone());
+
+    let set: HashSet<Tle> = vec![tle, renamed, broken_a, broken_b].into_iter().collect();
+    assert_eq!(set.len(), 3);
 }
@@ -0,0 +1,158 @@
+/*
+ * The MIT License (MIT)
+ *
+ * Copyright (c) 2015 Andres Vahter (andres.vahter@gmail.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+
+use ::predict::{Predict, PredictError};
+use ::sat::Sat;
+
+/// Rise/set edge detected by a `Tracker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackerEvent {
+    /// first sample above the horizon
+    Aos,
+    /// sample above the horizon, also reported for the AOS sample
+    Sample,
+    /// first sample below the horizon after a pass
+    Los,
+}
+
+/// Polls a `Predict` and turns the samples into AOS, sample and LOS events.
+///
+/// The horizon includes the AOS elevation, horizon mask, refraction and
+/// event hysteresis of the `Predict`.
+/// A pass already in progress at the first poll starts with an AOS.
+#[derive(Debug)]
+pub struct Tracker {
+    predict: Predict,
+    up: bool,
+}
+
+impl Tracker {
+    pub fn new(predict: Predict) -> Tracker {
+        Tracker { predict: predict, up: false }
+    }
+
+    pub fn predict(&self) -> &Predict {
+        &self.predict
+    }
+
+    pub fn predict_mut(&mut self) -> &mut Predict {
+        &mut self.predict
+    }
+
+    pub fn into_predict(self) -> Predict {
+        self.predict
+    }
+
+    /// Updates the satellite for time `t` and returns the resulting events
+    /// in order, `self.predict().sat` holds the sample.
+    pub fn poll_at(&mut self, t: DateTime<Utc>) -> Result<Vec<TrackerEvent>, PredictError> {
+        self.predict.update_at(t)?;
+
+        let up = self.predict.is_above_horizon(self.up);
+        let mut events = Vec::new();
+        if up && !self.up {
+            events.push(TrackerEvent::Aos);
+        }
+        if up {
+            events.push(TrackerEvent::Sample);
+        }
+        if !up && self.up {
+            events.push(TrackerEvent::Los);
+        }
+        self.up = up;
+
+        Ok(events)
+    }
+
+    /// Same as `poll_at` for the current time of the `Predict`'s clock.
+    pub fn poll_now(&mut self) -> Result<Vec<TrackerEvent>, PredictError> {
+        let now = self.predict.now();
+        self.poll_at(now)
+    }
+
+    /// Polls every `interval` and calls `on_event` for every event with the
+    /// current sample until it returns false or an update fails.
+    pub fn run<F>(&mut self, interval: Duration, mut on_event: F) -> Result<(), PredictError>
+        where F: FnMut(TrackerEvent, &Sat) -> bool
+    {
+        loop {
+            for event in self.poll_now()? {
+                if !on_event(event, &self.predict.sat) {
+                    return Ok(());
+                }
+            }
+
+            thread::sleep(interval);
+        }
+    }
+}
+
+#[test]
+fn tracker_rise_and_set() {
+    use ::clock::{from_julian_date, FixedClock};
+
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+
+    // culmination of a pass over Tartu
+    predict.set_clock(FixedClock(from_julian_date(2457266.81187298)));
+    let mut tracker = Tracker::new(predict);
+
+    assert_eq!(tracker.poll_now().unwrap(), vec![TrackerEvent::Aos, TrackerEvent::Sample]);
+    assert_eq!(tracker.poll_now().unwrap(), vec![TrackerEvent::Sample]);
+    assert_eq!(tracker.poll_at(from_julian_date(2457266.83)).unwrap(), vec![TrackerEvent::Los]);
+    assert_eq!(tracker.poll_at(from_julian_date(2457266.84)).unwrap(), vec![]);
+}
+
+#[test]
+fn tracker_hysteresis() {
+    use ::clock::{from_julian_date, to_julian_date};
+
+    let (tle, location) = ::grifex();
+    let mut predict = Predict::new(&tle, location).unwrap();
+    predict.set_event_hysteresis(0.5);
+
+    // first sample shortly after LOS which is a bit below the horizon
+    let culmination = from_julian_date(2457266.81187298);
+    predict.update_at(culmination).unwrap();
+    let mut jd = to_julian_date(predict.sat.los.unwrap());
+    while predict.observe_at(from_julian_date(jd)).el_deg > -0.2 {
+        jd += 1.0 / 86400.0;
+    }
+    assert!(predict.observe_at(from_julian_date(jd)).el_deg > -0.5);
+
+    let mut tracker = Tracker::new(predict);
+    assert_eq!(tracker.poll_at(culmination).unwrap(), vec![TrackerEvent::Aos, TrackerEvent::Sample]);
+    assert_eq!(tracker.poll_at(from_julian_date(jd)).unwrap(), vec![TrackerEvent::Sample]);
+    assert_eq!(tracker.poll_at(from_julian_date(2457266.83)).unwrap(), vec![TrackerEvent::Los]);
+
+    // without the margin the same sample ends the pass
+    tracker.predict_mut().set_event_hysteresis(0.);
+    tracker.poll_at(culmination).unwrap();
+    assert_eq!(tracker.poll_at(from_julian_date(jd)).unwrap(), vec![TrackerEvent::Los]);
+}